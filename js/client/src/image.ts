@@ -1,5 +1,6 @@
 import { formatLocalUrl } from '../../shared/url';
 import { PlayInfo } from '../../shared/types';
+import { authQuery } from './options';
 
 export function getImageUrl(info: PlayInfo): string | undefined {
   if (!info.image) return undefined;
@@ -9,7 +10,7 @@ export function getImageUrl(info: PlayInfo): string | undefined {
     return info.image;
   } else {
     return formatLocalUrl({
-      path: `/api/img/${info.image.id}/${info.image.epochId}`,
+      path: `/api/img/${info.image.id}/${info.image.epochId}${authQuery()}`,
       port: Number(location.port),
       host: location.hostname,
     });
@@ -23,7 +23,7 @@ import {
 } from '../../shared/reconnecting-websocket';
 import { makeCso2Env, startUserScript } from './user-scripts';
 import { MarqueeEl, MarqueeOptions, wrapMarquee } from './text/marquee';
-import { setupOptions } from './options';
+import { authQuery, setupOptions } from './options';
 
 // setup environment for plugins
 (globalThis as any).cso2 = makeCso2Env();
@@ -103,7 +103,7 @@ function wrapMarqueeElements(
 
   const ws = new ReconnectingWebsocket<IncomingMessages<EventMap>, OutgoingMessages>(
     formatLocalUrl({
-      path: '/api/ws/client',
+      path: `/api/ws/client${authQuery()}`,
       port: Number(location.port) || 48457,
       protocol: 'ws',
       host: location.hostname,
@@ -29,6 +29,8 @@ function applyOptions(el: HTMLElement) {
   };
 
   for (const [key, value] of query.entries()) {
+    // don't expose the token to the page
+    if (key === 'token') continue;
     addOpt('query', key, value);
   }
   for (const [key, value] of hash.entries()) {
@@ -58,6 +60,14 @@ export function setupOptions(): ScriptOptions {
   };
 }
 
+/**
+ * The query to authenticate requests to the API (`?token=...`) or an empty string if no token was specified.
+ */
+export function authQuery(): string {
+  const token = searchParams(location.search).get('token');
+  return token ? `?token=${encodeURIComponent(token)}` : '';
+}
+
 type VPos = 'top' | 'bottom' | 'center';
 type HPos = 'right' | 'left' | 'center';
 type Pos = { v: VPos | null; h: HPos | null };
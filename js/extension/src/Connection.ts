@@ -1,22 +1,62 @@
 import { DEFAULT_CURRENT_PORT, DEFAULT_LEGACY_PORT, listenOption, Option } from './options';
 import { formatLocalUrl } from '../../shared/url';
-import { LegacyEventData, MessageCreator } from './types/message.types';
+import {
+  ControlAction,
+  ControlCommand,
+  ControlResult,
+  LegacyEventData,
+  MessageCreator,
+} from './types/message.types';
 import { PlayInfo } from '../../shared/types';
 import { IncomingMessages, OutgoingMessages, ReconnectingWebsocket } from '../../shared/reconnecting-websocket';
 
 type ConnectionActiveMessage = LegacyEventData | PlayInfo;
 type Port = number | undefined | null;
 
+/** The version of the extension protocol this extension implements */
+const PROTOCOL_VERSION = 1;
+
 export class Connection {
   private sock?: ReconnectingWebsocket<
-    IncomingMessages,
-    OutgoingMessages<{ Active: ConnectionActiveMessage; Inactive: undefined }>
+    IncomingMessages<{
+      Control: ControlCommand;
+      Hello: { resumeToken: string; protocolVersion: number };
+      Error: { code: string; message: string };
+    }>,
+    OutgoingMessages<{
+      Active: ConnectionActiveMessage;
+      Inactive: undefined;
+      Hello: { protocolVersion: number };
+      Resume: { token: string; tabs: number[] };
+      Cover: { tab: number; url: string; contentType: string };
+      TabActive: { tab: number; info: PlayInfo };
+      TabInactive: { tab: number };
+      TabRemoved: { tab: number };
+      PreferTab: { tab: number | null };
+      ControlAck: { id: number };
+      ControlError: { id: number; unsupported: boolean; message: string };
+    }>
   >;
   private isLegacy = false;
   private port: Port = null;
   private host: string = '127.0.0.1';
+  private token: string | null = null;
 
   private lastMessage: null | ConnectionActiveMessage = null;
+  /** The last state of each tab (only used with the current API). */
+  private readonly lastTabs = new Map<number, PlayInfo | null>();
+  /** The last cover uploaded for each tab */
+  private readonly lastCovers = new Map<number, { url: string; contentType: string; data: Uint8Array }>();
+  /** The tab the server should show first (only used with the current API). */
+  private preferredTab: number | null = null;
+  /** Sent by the server to keep our modules when we reconnect. */
+  private resumeToken: string | null = null;
+
+  /**
+   * Called when the server wants to control the playback.
+   * `tab` is only set if the server controls a specific tab.
+   */
+  onControl: ((action: ControlAction, tab?: number) => Promise<ControlResult>) | null = null;
 
   constructor() {
     // although this may not seem like it,
@@ -24,44 +64,90 @@ export class Connection {
     listenOption<boolean>(Option.UseLegacyApi, v => this.handleOptionChange(!!v, this.host, this.port));
     listenOption<string>(Option.ApiHost, v => this.handleOptionChange(this.isLegacy, v || '127.0.0.1', this.port));
     listenOption<string>(Option.ApiPort, v => this.handleOptionChange(this.isLegacy, this.host, v ? Number(v) : null));
+    listenOption<string>(Option.ApiToken, v =>
+      this.handleOptionChange(this.isLegacy, this.host, this.port, v || null),
+    );
   }
 
-  handleOptionChange(isLegacy: boolean, host: string, port: Port) {
+  handleOptionChange(isLegacy: boolean, host: string, port: Port, token = this.token) {
     const legacyChanged = this.isLegacy !== isLegacy;
     const hostChanged = this.host !== host;
     const portChanged = this.port !== port;
-    if (!legacyChanged && !hostChanged && !portChanged && this.sock) return;
+    const tokenChanged = this.token !== token;
+    if (!legacyChanged && !hostChanged && !portChanged && !tokenChanged && this.sock) return;
 
     if (this.sock) this.sock.close();
     if (legacyChanged) this.lastMessage = null;
+    // the server removes the modules when we close the connection
+    this.resumeToken = null;
 
     this.port = port;
     this.host = host;
     this.isLegacy = isLegacy;
+    this.token = token;
 
     const actualPort = this.port ?? (this.isLegacy ? DEFAULT_LEGACY_PORT : DEFAULT_CURRENT_PORT);
     this.sock = new ReconnectingWebsocket(
       formatLocalUrl({
-        path: this.isLegacy ? '' : '/api/ws/extension',
+        path: this.isLegacy
+          ? ''
+          : `/api/ws/extension${this.token ? `?token=${encodeURIComponent(this.token)}` : ''}`,
         port: actualPort,
         protocol: 'ws',
         host: this.host,
       }),
     );
+    this.sock.addEventListener('Control', e => this.handleControl(e.data));
+    this.sock.addEventListener('Hello', e => this.handleHello(e.data));
+    this.sock.addEventListener('Error', e => console.warn(`Server rejected a message (${e.data.code}):`, e.data.message));
     this.sock.connect().then(() => {
-      if (this.lastMessage) {
+      // the current API sends a `Hello` on every connect
+      if (this.isLegacy && this.lastMessage) {
         this.sock?.trySend('Active', this.lastMessage);
       }
     });
   }
 
+  private handleHello({ resumeToken }: { resumeToken: string }) {
+    this.sock?.trySend('Hello', { protocolVersion: PROTOCOL_VERSION });
+    // tabs closed while we were disconnected aren't resumed
+    if (this.resumeToken) this.sock?.trySend('Resume', { token: this.resumeToken, tabs: [...this.lastTabs.keys()] });
+    this.resumeToken = resumeToken;
+
+    for (const [tab, info] of this.lastTabs) {
+      if (info) this.sock?.trySend('TabActive', { tab, info });
+      else this.sock?.trySend('TabInactive', { tab });
+    }
+    for (const [tab, { url, contentType, data }] of this.lastCovers) {
+      this.sendCoverMessages(tab, url, contentType, data);
+    }
+    this.sock?.trySend('PreferTab', { tab: this.preferredTab });
+  }
+
+  private async handleControl({ id, tab, ...action }: ControlCommand) {
+    const sock = this.sock;
+    let result: ControlResult;
+    try {
+      result = this.onControl
+        ? await this.onControl(action as ControlAction, tab)
+        : { ok: false, unsupported: true, message: 'Controls are not available' };
+    } catch (e) {
+      result = { ok: false, unsupported: false, message: String(e) };
+    }
+    if (sock !== this.sock) return; // reconnected in the meantime
+
+    if (result.ok) sock?.trySend('ControlAck', { id });
+    else sock?.trySend('ControlError', { id, unsupported: result.unsupported, message: result.message });
+  }
+
   /**
    * @param {MessageCreator | undefined} creator `undefined` implies 'Inactive'
    */
   send(creator?: MessageCreator) {
-    if (this.sock) {
+    // only the legacy API receives the overall state, the current API gets each tab through `sendTab`
+    if (this.sock && this.isLegacy) {
       if (creator) {
-        const message = this.isLegacy ? creator.createLegacyEvent() : creator.createPlayInfo();
+        const message = creator.createLegacyEvent();
         this.lastMessage = message;
 
         this.sock.trySend('Active', message);
@@ -71,4 +157,48 @@ export class Connection {
       }
     }
   }
+
+  /**
+   * Sends the state of a single tab (ignored with the legacy API).
+   *
+   * @param {MessageCreator | undefined} creator `undefined` implies that the tab is inactive
+   */
+  sendTab(tab: number, creator?: MessageCreator) {
+    const info = creator?.createPlayInfo() ?? null;
+    this.lastTabs.set(tab, info);
+    if (!this.sock || this.isLegacy) return;
+
+    if (info) this.sock.trySend('TabActive', { tab, info });
+    else this.sock.trySend('TabInactive', { tab });
+  }
+
+  /**
+   * Tells the server which tab to show if multiple tabs are playing (ignored with the legacy API).
+   */
+  preferTab(tab: number | null) {
+    if (this.preferredTab === tab) return;
+    this.preferredTab = tab;
+    if (this.sock && !this.isLegacy) this.sock.trySend('PreferTab', { tab });
+  }
+
+  /**
+   * Uploads the cover for an image URL of a tab (ignored with the legacy API).
+   * The server replaces the URL with the uploaded image.
+   */
+  sendCover(tab: number, url: string, contentType: string, data: Uint8Array) {
+    this.lastCovers.set(tab, { url, contentType, data });
+    if (!this.isLegacy) this.sendCoverMessages(tab, url, contentType, data);
+  }
+
+  private sendCoverMessages(tab: number, url: string, contentType: string, data: Uint8Array) {
+    // the binary message following `Cover` is the image
+    this.sock?.trySend('Cover', { tab, url, contentType });
+    this.sock?.trySendBinary(data);
+  }
+
+  removeTab(tab: number) {
+    this.lastTabs.delete(tab);
+    this.lastCovers.delete(tab);
+    if (this.sock && !this.isLegacy) this.sock.trySend('TabRemoved', { tab });
+  }
 }
@@ -9,13 +9,24 @@ interface TabManagerOptions {
   initialTabs: BrowserTab[];
   initialWindows: BrowserWindow[];
   updateCallback: (message: TabModel | null) => void;
+  /** Called for every valid tab (in addition to `updateCallback`). */
+  tabCallbacks?: TabCallbacks;
   filterManager: FilterManager;
   browser: IBrowserInterface;
 }
 
+export interface TabCallbacks {
+  /** `null` if the tab stopped being valid. */
+  update: (tabId: TabId, message: TabModel | null) => void;
+  /** A tab that was sent was closed. */
+  remove: (tabId: TabId) => void;
+}
+
 interface FindAndEmitOptions {
   forceSendIfActive?: boolean;
   forceSendIfNotActive?: boolean;
+  /** This tab's metadata changed and needs to be resent to the `tabCallbacks`. */
+  changedTabId?: TabId;
 }
 
 export class TabManager extends EventTarget {
@@ -28,16 +39,20 @@ export class TabManager extends EventTarget {
   activeWindowId: number | null = null;
 
   sentTabId: number | null = null;
+  /** Tabs that were sent to the `tabCallbacks` as valid. */
+  readonly sentTabIds = new Set<TabId>();
 
   private shouldTrackActiveWindow = false;
 
   private readonly updateCallback: (message: TabModel | null) => void;
+  private readonly tabCallbacks?: TabCallbacks;
   private readonly filterManager: FilterManager;
   private readonly browser: IBrowserInterface;
 
-  constructor({ initialTabs, initialWindows, updateCallback, filterManager, browser }: TabManagerOptions) {
+  constructor({ initialTabs, initialWindows, updateCallback, tabCallbacks, filterManager, browser }: TabManagerOptions) {
     super();
     this.updateCallback = updateCallback;
+    this.tabCallbacks = tabCallbacks;
     this.browser = browser;
     this.filterManager = filterManager;
     this.filterManager.setUpdateListener(() => this.filtersUpdated());
@@ -70,6 +85,7 @@ export class TabManager extends EventTarget {
     model.updateTimeline(position);
 
     if (this.sentTabId === model.id) this.updateCallback(model);
+    if (this.sentTabIds.has(model.id)) this.tabCallbacks?.update(model.id, model);
   }
 
   setMetadata(tab: BrowserTab, meta?: VideoMetadata) {
@@ -81,6 +97,7 @@ export class TabManager extends EventTarget {
     if (!anyChange) return;
 
     if (this.sentTabId === model.id) this.updateCallback(model);
+    if (this.sentTabIds.has(model.id)) this.tabCallbacks?.update(model.id, model);
   }
 
   private addTab(tab: BrowserTab): TabModel {
@@ -133,6 +150,7 @@ export class TabManager extends EventTarget {
 
   private tabRemoved(tabId: TabId) {
     this.tabs.delete(tabId);
+    if (this.sentTabIds.delete(tabId)) this.tabCallbacks?.remove(tabId);
 
     this.findAndEmitActiveTab();
   }
@@ -156,7 +174,7 @@ export class TabManager extends EventTarget {
       // We still need to check for filters though.
       this.findAndEmitActiveTab();
     } else {
-      this.findAndEmitActiveTab({ forceSendIfActive: browserTab.id === this.sentTabId });
+      this.findAndEmitActiveTab({ forceSendIfActive: browserTab.id === this.sentTabId, changedTabId: tabId });
     }
   }
 
@@ -181,7 +199,9 @@ export class TabManager extends EventTarget {
     this.findAndEmitActiveTab();
   }
 
-  private findAndEmitActiveTab({ forceSendIfActive, forceSendIfNotActive }: FindAndEmitOptions = {}) {
+  private findAndEmitActiveTab({ forceSendIfActive, forceSendIfNotActive, changedTabId }: FindAndEmitOptions = {}) {
+    this.emitAllTabs(changedTabId);
+
     // optimization?: The last sent tab is still valid and exists, and we don't need to resend (forceSendIfActive).
     // !forceSendIfActive && this.sentTabId !== null && this.tabs.has(this.sentTabId) && this.isValidTab(this.tabs.get(this.sentTabId!)!)
 
@@ -200,6 +220,23 @@ export class TabManager extends EventTarget {
     this.updateCallback(audible);
   }
 
+  private emitAllTabs(changedTabId?: TabId) {
+    if (!this.tabCallbacks) return;
+
+    for (const tab of this.tabs.values()) {
+      const wasSent = this.sentTabIds.has(tab.id);
+      if (this.isValidTab(tab)) {
+        if (wasSent && tab.id !== changedTabId) continue;
+
+        this.sentTabIds.add(tab.id);
+        this.tabCallbacks.update(tab.id, tab);
+      } else if (wasSent) {
+        this.sentTabIds.delete(tab.id);
+        this.tabCallbacks.update(tab.id, null);
+      }
+    }
+  }
+
   private isValidTab(tab: TabModel): boolean {
     if (
       !this.filterManager.includeFocusedTabs &&
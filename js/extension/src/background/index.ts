@@ -7,12 +7,22 @@ import { Connection } from '../Connection';
 import { FilterManager } from '../filters/FilterManager';
 import { LocalFilterStorage } from '../filters/FilterStorage';
 import { DefaultBrowserInterface } from './BrowserInterface';
+import { CONTROL_MESSAGE, ControlResult } from '../types/message.types';
 
 (async () => {
   (globalThis as any).csDebug = { sends: false };
   const connection = new Connection();
 
   const manager = await createManager(connection);
+  connection.onControl = async (action, tab) => {
+    const tabId = tab ?? manager.sentTabId;
+    if (tabId === null) return { ok: false, unsupported: false, message: 'No tab is playing' };
+    const result: ControlResult | undefined = await browser.tabs.sendMessage(tabId, {
+      type: CONTROL_MESSAGE,
+      data: action,
+    });
+    return result ?? { ok: false, unsupported: true, message: "The tab can't be controlled" };
+  };
 
   const events = new BackgroundEventHandler<InternalMessageMap>();
   events.on('PlayPosition', (data, sender) => {
@@ -21,6 +31,11 @@ import { DefaultBrowserInterface } from './BrowserInterface';
   events.on('Metadata', (data, sender) => {
     if (sender.tab) manager.setMetadata(sender.tab, data || undefined);
   });
+  events.on('Artwork', ({ url, contentType, data }, sender) => {
+    if (typeof sender.tab?.id !== 'number') return;
+    const bytes = Uint8Array.from(atob(data), c => c.charCodeAt(0));
+    connection.sendCover(sender.tab.id, url, contentType, bytes);
+  });
 })();
 
 async function createManager(connection: Connection): Promise<TabManager> {
@@ -32,6 +47,11 @@ async function createManager(connection: Connection): Promise<TabManager> {
       if ((globalThis as any).csDebug?.sends)
         console.trace('%cSend %o', 'color: red; font-weight: bold; font-size: 2em;', message);
       connection.send(message ?? undefined);
+      connection.preferTab(message?.id ?? null);
+    },
+    tabCallbacks: {
+      update: (tabId, message) => connection.sendTab(tabId, message ?? undefined),
+      remove: tabId => connection.removeTab(tabId),
     },
     initialWindows,
     filterManager: new FilterManager(LocalFilterStorage),
@@ -1,7 +1,8 @@
 import { ContextEventHandler } from 'beaverjs';
-import { InternalMessageMap } from '../messages';
+import { ArtworkData, InternalMessageMap } from '../messages';
 import { VideoPlayPosition } from '../types/video.types';
 import { safeInject } from '../utils/safe-inject';
+import { CONTROL_MESSAGE, CONTROL_RESULT_MESSAGE, ControlAction } from '../types/message.types';
 
 (async () => {
   if (!navigator.mediaSession) return;
@@ -15,15 +16,24 @@ import { safeInject } from '../utils/safe-inject';
     playbackState: MediaSessionPlaybackState | undefined,
     metadata: MediaMetadata | undefined | null;
 
+  let uploadedArtwork: string | undefined;
+
   const onUpdate = () => {
     events.emitBackground('PlayMode', playbackState ?? 'none');
     if (metadata) {
+      const artwork = metadata.artwork?.[0]?.src; // TODO: get best image
       events.emitBackground('Metadata', {
         title: metadata.title,
         artist: metadata.artist,
-        artwork: metadata.artwork?.[0]?.src, // TODO: get best image
+        artwork,
         album: metadata.album,
       });
+      if (artwork && artwork !== uploadedArtwork && needsUpload(artwork)) {
+        uploadedArtwork = artwork;
+        fetchArtwork(artwork)
+          .then(data => data && events.emitBackground('Artwork', data))
+          .catch(e => console.warn('[CSO2] Failed to fetch artwork', e));
+      }
     }
     if (positionState) {
       events.emitBackground('PlayPosition', positionState);
@@ -45,12 +55,106 @@ import { safeInject } from '../utils/safe-inject';
     onUpdate();
   });
 
+  const actionHandlers = new Map<MediaSessionAction, MediaSessionActionHandler>();
+  interceptFunction(proto, 'setActionHandler', (action: MediaSessionAction, handler: MediaSessionActionHandler | null) => {
+    if (handler) actionHandlers.set(action, handler);
+    else actionHandlers.delete(action);
+  });
+
+  window.addEventListener(CONTROL_MESSAGE, e => {
+    const { requestId, action }: { requestId: number; action: ControlAction } = JSON.parse(
+      (e as CustomEvent<string>).detail,
+    );
+    let result;
+    try {
+      result = { requestId, handled: runControl(action, actionHandlers, playbackState, positionState) };
+    } catch (err) {
+      console.warn('[CSO2] Failed to run control', err);
+      result = { requestId, handled: false, error: String(err) };
+    }
+    window.dispatchEvent(new CustomEvent(CONTROL_RESULT_MESSAGE, { detail: JSON.stringify(result) }));
+  });
+
   window.addEventListener('beforeunload', () => {
     events.emitBackground('PlayPosition', null);
     events.emitBackground('Metadata', null);
   });
 })();
 
+/** The server accepts messages up to 4 MiB. */
+const MAX_ARTWORK_SIZE = 3 * 1024 * 1024;
+
+/** Whether the server (or OBS) can't fetch the URL on its own. */
+function needsUpload(url: string): boolean {
+  try {
+    const parsed = new URL(url, location.href);
+    // same-origin images might require cookies
+    return parsed.protocol === 'blob:' || parsed.protocol === 'data:' || parsed.origin === location.origin;
+  } catch {
+    return false;
+  }
+}
+
+async function fetchArtwork(url: string): Promise<ArtworkData | null> {
+  const response = await fetch(url, { credentials: 'include' });
+  const blob = await response.blob();
+  if (!response.ok || !blob.type.startsWith('image/') || blob.size > MAX_ARTWORK_SIZE) return null;
+
+  const bytes = new Uint8Array(await blob.arrayBuffer());
+  let binary = '';
+  for (const byte of bytes) binary += String.fromCharCode(byte);
+  return { url, contentType: blob.type, data: btoa(binary) };
+}
+
+/**
+ * Runs a control with the page's action handlers or falls back to the media element.
+ * @returns {boolean} false if the frame can't run the control.
+ */
+function runControl(
+  control: ControlAction,
+  handlers: Map<MediaSessionAction, MediaSessionActionHandler>,
+  playbackState: MediaSessionPlaybackState | undefined,
+  positionState: Omit<VideoPlayPosition, 'mode'> | undefined,
+): boolean {
+  const media = document.querySelector<HTMLMediaElement>('video, audio');
+  const call = (action: MediaSessionAction, details: Partial<MediaSessionActionDetails> = {}) => {
+    const handler = handlers.get(action);
+    if (!handler) return false;
+    handler({ action, ...details } as MediaSessionActionDetails);
+    return true;
+  };
+  const play = () => call('play') || (media ? (media.play(), true) : false);
+  const pause = () => call('pause') || (media ? (media.pause(), true) : false);
+  const seekTo = (positionMs: number) => {
+    const position = Math.max(0, positionMs);
+    if (call('seekto', { seekTime: position / 1000 })) return true;
+    if (!media) return false;
+    media.currentTime = position / 1000;
+    return true;
+  };
+
+  switch (control.action) {
+    case 'play':
+      return play();
+    case 'pause':
+      return pause();
+    case 'toggle': {
+      const playing = playbackState ? playbackState === 'playing' : media && !media.paused;
+      return playing ? pause() : play();
+    }
+    case 'next':
+      return call('nexttrack');
+    case 'previous':
+      return call('previoustrack');
+    case 'seek': {
+      const current = media ? media.currentTime * 1000 : positionState?.position;
+      return current !== undefined && seekTo(current + control.offsetMs);
+    }
+    case 'seekTo':
+      return seekTo(control.positionMs);
+  }
+}
+
 function toPlayPosition(pos: MediaPositionState): VideoPlayPosition {
   return {
     timestamp: Date.now(),
@@ -1,11 +1,18 @@
 import '../chrome-fix';
 import { ContentEventHandler } from 'beaverjs';
+import { CONTROL_MESSAGE, CONTROL_RESULT_MESSAGE, ControlResult } from '../types/message.types';
+
+/** Frames that can't run a control answer after this delay, so a frame that can has a chance to answer first. */
+const UNHANDLED_DELAY = 500;
 
 if (document.documentElement instanceof HTMLElement) {
   try {
     // act as a proxy
     // we only want to pass events to the background
     new ContentEventHandler(['context']);
+    browser.runtime.onMessage.addListener((message: any) => {
+      if (message?.type === CONTROL_MESSAGE) return runInPage(message.data);
+    });
     const el = document.createElement('script');
     el.src = browser.runtime.getURL('content-scripts/mediaSessionProxy.inject.js');
     el.onload = () => el.remove();
@@ -16,3 +23,30 @@ if (document.documentElement instanceof HTMLElement) {
 } else {
   console.debug("%c[CSO2] %cDocument isn't an HTML Document, skipping injection.", 'color: red', 'color: yellow');
 }
+
+let nextRequestId = 0;
+
+/**
+ * Forwards a control to the page and waits for its result.
+ * The details are passed as JSON to cross the context boundary.
+ */
+function runInPage(action: unknown): Promise<ControlResult> {
+  const requestId = nextRequestId++;
+  return new Promise(resolve => {
+    const unhandled = () => resolve({ ok: false, unsupported: true, message: 'No media session in this frame' });
+    // the page script might not be injected (e.g. no media session support)
+    const timeoutId = setTimeout(unhandled, UNHANDLED_DELAY);
+    const listener = (e: Event) => {
+      const result = JSON.parse((e as CustomEvent<string>).detail);
+      if (result.requestId !== requestId) return;
+      window.removeEventListener(CONTROL_RESULT_MESSAGE, listener);
+      clearTimeout(timeoutId);
+
+      if (result.error) resolve({ ok: false, unsupported: false, message: result.error });
+      else if (result.handled) resolve({ ok: true });
+      else setTimeout(unhandled, UNHANDLED_DELAY);
+    };
+    window.addEventListener(CONTROL_RESULT_MESSAGE, listener);
+    window.dispatchEvent(new CustomEvent(CONTROL_MESSAGE, { detail: JSON.stringify({ requestId, action }) }));
+  });
+}
@@ -4,4 +4,14 @@ export type InternalMessageMap = {
   PlayPosition: VideoPlayPosition | null;
   Metadata: VideoMetadata | null;
   PlayMode: VideoPlayMode;
+  /** Artwork the server can't fetch on its own (e.g. `blob:` URLs) */
+  Artwork: ArtworkData;
 };
+
+export interface ArtworkData {
+  /** The URL in the metadata */
+  url: string;
+  contentType: string;
+  /** base64 encoded */
+  data: string;
+}
@@ -7,6 +7,7 @@ export enum Option {
   IncludeFocusedTabs = 'include-focused-tabs',
   ApiPort = 'api-port',
   ApiHost = 'api-host',
+  ApiToken = 'api-token',
 }
 
 export enum FilterMode {
@@ -10,3 +10,22 @@ export interface LegacyEventData {
   metadata: VideoMetadata;
   position?: VideoPlayPosition;
 }
+
+/** A control action sent by the server (`Control` message). */
+export type ControlAction =
+  | { action: 'play' | 'pause' | 'toggle' | 'next' | 'previous' }
+  | { action: 'seek'; offsetMs: number }
+  | { action: 'seekTo'; positionMs: number };
+
+/**
+ * The server expects a `ControlAck` or `ControlError` with the same `id`.
+ * `tab` is the tab of the controlled module.
+ */
+export type ControlCommand = ControlAction & { id: number; tab?: number };
+
+export type ControlResult = { ok: true } | { ok: false; unsupported: boolean; message: string };
+
+/** Sent from the background to the content scripts of a tab. */
+export const CONTROL_MESSAGE = 'cso2:control';
+/** Sent from the page to the content script after running a control. */
+export const CONTROL_RESULT_MESSAGE = 'cso2:control-result';
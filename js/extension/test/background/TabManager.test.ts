@@ -298,6 +298,29 @@ describe('TabManager', function () {
     });
   });
 
+  describe('tab callbacks', function () {
+    it('should emit every valid tab', async function () {
+      const { browser, initialTabs, initialWindows, removeTab, changeTab } = make2Wind4TabBrowser();
+      const { nextUpdate, manager, tabEvents } = mockTabManager({ browser, initialTabs, initialWindows });
+      await expectTab(nextUpdate(), 12);
+      expect(tabEvents.splice(0)).toEqual(['update 12', 'update 13', 'update 21', 'update 22', 'update 23']);
+
+      manager.setMetadata(mockTab(21, 2), { title: 'aliens', artist: 'pleased' });
+      expect(tabEvents.splice(0)).toEqual(['update 21']);
+
+      changeTab(13, tab => (tab.mutedInfo = { muted: true }));
+      await waitNMicrotasks(10);
+      expect(tabEvents.splice(0)).toEqual(['inactive 13']);
+
+      const promise = nextUpdate();
+      removeTab(12);
+      await expectTab(promise, 21);
+      removeTab(13);
+      await waitNMicrotasks(10);
+      expect(tabEvents.splice(0)).toEqual(['remove 12']);
+    });
+  });
+
   describe('invalid input', function () {
     it('should reject invalid tabs', async function () {
       const { browser, initialTabs, initialWindows, focusWindow } = make1Wind1TabBrowser();
@@ -21,6 +21,8 @@ export function mockTabManager({
 }) {
   let resolveFn: UpdateCallback | null = null;
   const nextUpdate = () => new Promise<TabModel | null>(res => (resolveFn = res));
+  /** Events of the `tabCallbacks` as `update <id>`, `inactive <id>` or `remove <id>` */
+  const tabEvents: string[] = [];
 
   return {
     manager: new TabManager({
@@ -33,8 +35,13 @@ export function mockTabManager({
         resolveFn(msg);
         resolveFn = null;
       },
+      tabCallbacks: {
+        update: (id, msg) => tabEvents.push(`${msg ? 'update' : 'inactive'} ${id}`),
+        remove: id => tabEvents.push(`remove ${id}`),
+      },
     }),
     nextUpdate,
+    tabEvents,
   };
 }
 
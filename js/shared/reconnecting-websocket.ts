@@ -132,6 +132,14 @@ export class ReconnectingWebsocket<EventMap extends MinEventMap, SendMap extends
     }
   }
 
+  public trySendBinary(data: ArrayBuffer | Uint8Array) {
+    try {
+      this.ws?.send(data);
+    } catch (e) {
+      console.warn('Error sending websocket message:', e);
+    }
+  }
+
   public close() {
     this.shouldClose = true;
     this.ws?.close();
@@ -8,6 +8,9 @@ export interface PlayInfo {
   album: null | AlbumInfo;
 
   source: string;
+
+  titleLatin?: string;
+  artistLatin?: string;
 }
 
 export type ImageInfo = string | InternalImage;
@@ -1,5 +1,5 @@
 #[cfg(unix)]
-use mpris_dbus::player;
+use mpris_dbus::PlayerRegistry;
 
 #[cfg(unix)]
 #[tokio::main]
@@ -8,7 +8,9 @@ async fn main() {
         .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
         .init();
 
-    let mut listener = player::listen("org.mpris.MediaPlayer2.spotify")
+    let registry = PlayerRegistry::session().await.unwrap();
+    let (mut listener, _controls) = registry
+        .listen("org.mpris.MediaPlayer2.spotify")
         .await
         .unwrap();
     println!("Waiting for events...");
@@ -3,8 +3,6 @@ use zbus::Connection;
 
 #[derive(Debug, Clone, PartialEq, thiserror::Error)]
 pub enum Error {
-    #[error("Failed to get DBus connection to the user message bus ({0})")]
-    GetConnection(zbus::Error),
     #[error("Failed to setup the proxy ({0})")]
     SetupProxy(zbus::Error),
     #[error("Failed to listen to the name acquired stream ({0})")]
@@ -13,42 +11,60 @@ pub enum Error {
     ListNames(zbus::fdo::Error),
 }
 
+/// A change of a bus name's owner.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NameEvent {
+    /// The name has an owner now.
+    Appeared(zbus_names::BusName<'static>),
+    /// The name lost its owner.
+    Vanished(zbus_names::BusName<'static>),
+}
+
 pub struct Listener {
     conn: Connection,
 }
 
 impl Listener {
-    pub async fn new() -> Result<Self, Error> {
-        Ok(Self {
-            conn: Connection::session().await.map_err(Error::GetConnection)?,
-        })
+    pub fn new(conn: Connection) -> Self {
+        Self { conn }
     }
 
-    pub async fn listen(
-        &self,
-    ) -> Result<impl futures::Stream<Item = zbus_names::BusName<'static>>, Error> {
+    /// Emits [`NameEvent::Appeared`] for all current names, followed by all owner changes.
+    ///
+    /// If a name changes its owner directly, it vanishes and appears again.
+    pub async fn listen(&self) -> Result<impl futures::Stream<Item = NameEvent>, Error> {
         let proxy = zbus::fdo::DBusProxy::new(&self.conn)
             .await
             .map_err(Error::SetupProxy)?;
 
+        // subscribe first, so names acquired while listing them aren't missed
+        let changes = proxy
+            .receive_name_owner_changed()
+            .await
+            .map_err(Error::ListenNameAcquired)?;
         let current = proxy.list_names().await.map_err(Error::ListNames)?;
 
-        Ok(
-            futures::stream::iter(current.into_iter().map(|it| it.into_inner())).chain(
-                proxy
-                    .receive_name_owner_changed()
-                    .await
-                    .map_err(Error::ListenNameAcquired)?
-                    .filter_map(|it| {
-                        std::future::ready(it.args().ok().and_then(|n| {
-                            if n.new_owner.is_some() {
-                                Some(n.name.into_owned())
-                            } else {
-                                None
-                            }
-                        }))
-                    }),
-            ),
+        Ok(futures::stream::iter(
+            current
+                .into_iter()
+                .map(|it| NameEvent::Appeared(it.into_inner())),
         )
+        .chain(changes.flat_map(|it| {
+            let events = match it.args() {
+                Ok(args) => {
+                    let name = args.name.into_owned();
+                    let mut events = Vec::with_capacity(2);
+                    if args.old_owner.is_some() {
+                        events.push(NameEvent::Vanished(name.clone()));
+                    }
+                    if args.new_owner.is_some() {
+                        events.push(NameEvent::Appeared(name));
+                    }
+                    events
+                }
+                Err(_) => Vec::new(),
+            };
+            futures::stream::iter(events)
+        })))
     }
 }
@@ -3,3 +3,8 @@
 pub mod discovery;
 pub mod interface;
 pub mod player;
+mod registry;
+#[cfg(feature = "testing")]
+pub mod testing;
+
+pub use registry::PlayerRegistry;
@@ -0,0 +1,70 @@
+use crate::interface::MediaPlayerProxy;
+use zbus::fdo;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Play,
+    Pause,
+    PlayPause,
+    Next,
+    Previous,
+    /// Seeks by an offset in microseconds.
+    Seek(i64),
+    /// Seeks to a position in microseconds.
+    SeekTo(i64),
+}
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum ControlError {
+    #[error("The player doesn't support this command")]
+    NotSupported,
+    #[error("The command failed: {0}")]
+    Failed(fdo::Error),
+}
+
+/// Sends commands to a player.
+#[derive(Debug, Clone)]
+pub struct Controls {
+    proxy: MediaPlayerProxy<'static>,
+}
+
+impl Controls {
+    pub(crate) fn new(proxy: MediaPlayerProxy<'static>) -> Self {
+        Self { proxy }
+    }
+
+    /// Executes `command` if the player reports that it's supported.
+    pub async fn execute(&self, command: Command) -> Result<(), ControlError> {
+        let supported = match command {
+            Command::Play => self.proxy.can_play().await,
+            Command::Pause | Command::PlayPause => self.proxy.can_pause().await,
+            Command::Next => self.proxy.can_go_next().await,
+            Command::Previous => self.proxy.can_go_previous().await,
+            Command::Seek(_) | Command::SeekTo(_) => self.proxy.can_seek().await,
+        };
+        // players without the property might still support the command
+        if supported == Ok(false) {
+            return Err(ControlError::NotSupported);
+        }
+
+        let result = match command {
+            Command::Play => self.proxy.play().await,
+            Command::Pause => self.proxy.pause().await,
+            Command::PlayPause => self.proxy.play_pause().await,
+            Command::Next => self.proxy.next().await,
+            Command::Previous => self.proxy.previous().await,
+            Command::Seek(offset) => self.proxy.seek(offset).await,
+            // SetPosition requires the track id, so this seeks relative to the current position
+            Command::SeekTo(position) => match self.proxy.position().await {
+                Ok(current) => self.proxy.seek(position - current).await,
+                Err(e) => Err(e),
+            },
+        };
+        result.map_err(|e| match e {
+            fdo::Error::NotSupported(_) | fdo::Error::UnknownMethod(_) => {
+                ControlError::NotSupported
+            }
+            e => ControlError::Failed(e),
+        })
+    }
+}
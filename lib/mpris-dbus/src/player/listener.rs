@@ -1,6 +1,9 @@
 use std::collections::HashMap;
 
-use crate::{interface::*, player::State};
+use crate::{
+    interface::*,
+    player::{Controls, State},
+};
 use chrono::Utc;
 use futures::StreamExt;
 use tap::TapFallible;
@@ -10,10 +13,12 @@ use zbus::{zvariant, Connection};
 
 #[derive(Debug, Clone, PartialEq, thiserror::Error)]
 pub enum Error {
-    #[error("Failed to get DBus connection to the user message bus")]
-    GetConnection(zbus::Error),
     #[error("Failed to setup the proxy")]
     SetupProxy(zbus::Error),
+    #[error("Failed to listen to the seeked signal ({0})")]
+    ListenSeeked(zbus::Error),
+    #[error("Failed to listen to owner changes ({0})")]
+    ListenOwnerChanged(zbus::Error),
 }
 
 macro_rules! send_or_break {
@@ -24,34 +29,50 @@ macro_rules! send_or_break {
     };
 }
 
-pub async fn listen<D>(dest: D) -> Result<mpsc::Receiver<State>, Error>
+/// Listens to updates of the player at `dest` on `connection`.
+pub async fn listen<D>(
+    connection: &Connection,
+    dest: D,
+) -> Result<(mpsc::Receiver<State>, Controls), Error>
 where
     D: TryInto<zbus::names::BusName<'static>>,
     D::Error: Into<zbus::Error>,
 {
-    let connection = Connection::session().await.map_err(Error::GetConnection)?;
-    let proxy = MediaPlayerProxy::builder(&connection)
+    let proxy = MediaPlayerProxy::builder(connection)
         .destination(dest)
         .map_err(Error::SetupProxy)?
         .build()
         .await
         .map_err(Error::SetupProxy)?;
 
+    let mut seeked = proxy.receive_seeked().await.map_err(Error::ListenSeeked)?;
+    let mut owner_changed = proxy
+        .inner()
+        .receive_owner_changed()
+        .await
+        .map_err(Error::ListenOwnerChanged)?;
+
     let (tx, rx) = mpsc::channel(8);
+    let controls = Controls::new(proxy.clone());
 
     tokio::spawn(async move {
         let mut status_changed = proxy.receive_playback_status_changed().await;
         let mut meta_changed = proxy.receive_metadata_changed().await;
         let mut rate_changed = proxy.receive_rate_changed().await;
-        let mut seeked = proxy.receive_seeked().await.expect("TODO");
-        let mut owner_changed = proxy.inner().receive_owner_changed().await.expect("TODO");
 
-        let mut state = State::default();
+        // the player might be playing already
+        let mut state = initial_state(&proxy).await;
+        if tx.send(state.clone()).await.is_err() {
+            return;
+        }
 
         loop {
             tokio::select! { biased;
+                // reading the initial state populates the property cache, which emits the
+                // current values again
                 Some(status) = status_changed.next() => {
                     match status.get().await {
+                        Ok(s) if s == state.status => (),
                         Ok(s) => {
                             state.status = s;
                             send_or_break!(tx, state.clone())
@@ -60,11 +81,15 @@ where
                     };
                 },
                 Some(_) = meta_changed.next() => {
+                    let previous = state.clone();
                     update_meta(&proxy, &mut state).await;
-                    send_or_break!(tx, state.clone());
+                    if changed(&previous, &state) {
+                        send_or_break!(tx, state.clone());
+                    }
                 },
                 Some(rate) = rate_changed.next() => {
                     match rate.get().await {
+                        Ok(r) if r == state.playback_rate => (),
                         Ok(r) => {
                             state.playback_rate = r;
                             send_or_break!(tx, state.clone())
@@ -88,7 +113,29 @@ where
         info!("loop ended");
     });
 
-    Ok(rx)
+    Ok((rx, controls))
+}
+
+/// Reads the current state of the player.
+async fn initial_state(proxy: &MediaPlayerProxy<'_>) -> State {
+    let mut state = State::default();
+    match proxy.playback_status().await {
+        Ok(status) => state.status = status,
+        Err(e) => warn!(error = %e, "Failed to get status"),
+    }
+    match proxy.rate().await {
+        Ok(rate) => state.playback_rate = rate,
+        Err(e) => warn!(error = %e, "Failed to get rate"),
+    }
+    update_meta(proxy, &mut state).await;
+    state
+}
+
+/// Whether `state` differs from `previous` (ignoring when the position was read).
+fn changed(previous: &State, state: &State) -> bool {
+    let mut previous = previous.clone();
+    previous.timeline.ts = state.timeline.ts;
+    previous != *state
 }
 
 async fn update_meta(proxy: &MediaPlayerProxy<'_>, state: &mut State) {
@@ -2,9 +2,12 @@ use chrono::{DateTime, Utc};
 
 use crate::interface::PlaybackStatus;
 
+mod controls;
 mod listener;
 
-pub use listener::{listen, Error as ListenError};
+pub use controls::{Command, ControlError, Controls};
+pub(crate) use listener::listen;
+pub use listener::Error as ListenError;
 
 #[derive(Debug, Clone, PartialEq, Default)]
 pub struct State {
@@ -0,0 +1,45 @@
+use crate::{
+    discovery::{self, NameEvent},
+    player::{self, Controls, ListenError, State},
+};
+use tokio::sync::mpsc;
+use zbus::Connection;
+
+/// Hands out the name events and the per-player streams of a single D-Bus connection.
+///
+/// Cloning the registry shares the connection.
+#[derive(Debug, Clone)]
+pub struct PlayerRegistry {
+    conn: Connection,
+}
+
+impl PlayerRegistry {
+    pub fn new(conn: Connection) -> Self {
+        Self { conn }
+    }
+
+    /// Connects to the session bus.
+    pub async fn session() -> zbus::Result<Self> {
+        Ok(Self::new(Connection::session().await?))
+    }
+
+    pub fn connection(&self) -> &Connection {
+        &self.conn
+    }
+
+    /// See [`discovery::Listener::listen`].
+    pub async fn names(&self) -> Result<impl futures::Stream<Item = NameEvent>, discovery::Error> {
+        discovery::Listener::new(self.conn.clone()).listen().await
+    }
+
+    /// Listens to updates of the player at `dest`.
+    ///
+    /// The returned [`Controls`] can be used to control the player.
+    pub async fn listen<D>(&self, dest: D) -> Result<(mpsc::Receiver<State>, Controls), ListenError>
+    where
+        D: TryInto<zbus::names::BusName<'static>>,
+        D::Error: Into<zbus::Error>,
+    {
+        player::listen(&self.conn, dest).await
+    }
+}
@@ -0,0 +1,155 @@
+//! A private session bus with mock players for tests.
+
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, BufReader},
+    process::{Child, Command, Stdio},
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+use zbus::{interface, object_server::InterfaceRef, zvariant, Connection};
+
+pub const PLAYER_PATH: &str = "/org/mpris/MediaPlayer2";
+
+/// Set to skip tests that need a bus when `dbus-daemon` isn't installed.
+pub const SKIP_ENV: &str = "MPRIS_DBUS_SKIP_TESTS";
+
+/// Held by the current [`Bus`], because it sets the session bus of the whole process.
+static BUS_LOCK: Mutex<()> = Mutex::new(());
+
+/// A `dbus-daemon` that's killed when dropped.
+///
+/// Only one bus exists at a time, other tests wait in [`Bus::start`].
+pub struct Bus {
+    daemon: Child,
+    pub address: String,
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl Bus {
+    /// Starts a session bus and makes it the default one of this process.
+    ///
+    /// Returns `None` if `dbus-daemon` can't be started and [`SKIP_ENV`] is set.
+    ///
+    /// # Panics
+    ///
+    /// If `dbus-daemon` can't be started and [`SKIP_ENV`] isn't set.
+    pub fn start() -> Option<Self> {
+        let lock = BUS_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+        let (daemon, address) = match spawn_daemon() {
+            Ok(daemon) => daemon,
+            Err(e) if std::env::var_os(SKIP_ENV).is_some() => {
+                eprintln!("Skipping test, can't start dbus-daemon: {e}");
+                return None;
+            }
+            Err(e) => panic!("Can't start dbus-daemon ({e}), set {SKIP_ENV}=1 to skip this test"),
+        };
+        Some(Self {
+            daemon,
+            address,
+            _lock: lock,
+        })
+    }
+
+    /// Kills the daemon, so all connections to it are lost.
+    pub fn stop(&mut self) {
+        self.daemon.kill().ok();
+        self.daemon.wait().ok();
+    }
+
+    /// Replaces the daemon with a new one (at a new address).
+    pub fn restart(&mut self) {
+        self.stop();
+        (self.daemon, self.address) = spawn_daemon().unwrap();
+    }
+
+    /// Serves `player` as `name` on a new connection.
+    pub async fn start_player(&self, name: &str, player: MockPlayer) -> Connection {
+        zbus::connection::Builder::address(self.address.as_str())
+            .unwrap()
+            .name(name.to_owned())
+            .unwrap()
+            .serve_at(PLAYER_PATH, player)
+            .unwrap()
+            .build()
+            .await
+            .unwrap()
+    }
+}
+
+impl Drop for Bus {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn spawn_daemon() -> io::Result<(Child, String)> {
+    let mut daemon = Command::new("dbus-daemon")
+        .args(["--session", "--nofork", "--print-address"])
+        .stdout(Stdio::piped())
+        .spawn()?;
+    let mut address = String::new();
+    BufReader::new(daemon.stdout.take().unwrap()).read_line(&mut address)?;
+    let address = address.trim().to_owned();
+    std::env::set_var("DBUS_SESSION_BUS_ADDRESS", &address);
+    Ok((daemon, address))
+}
+
+#[derive(Debug, Clone)]
+pub struct MockPlayer {
+    pub status: &'static str,
+    pub title: String,
+    pub artists: Vec<String>,
+    /// Length in microseconds.
+    pub length: i64,
+    pub rate: f64,
+    /// Position in microseconds.
+    pub position: i64,
+}
+
+impl Default for MockPlayer {
+    fn default() -> Self {
+        Self {
+            status: "Stopped",
+            title: String::new(),
+            artists: Vec::new(),
+            length: 0,
+            rate: 1.0,
+            position: 0,
+        }
+    }
+}
+
+#[interface(name = "org.mpris.MediaPlayer2.Player")]
+impl MockPlayer {
+    #[zbus(property)]
+    fn playback_status(&self) -> &str {
+        self.status
+    }
+
+    #[zbus(property)]
+    fn metadata(&self) -> HashMap<&str, zvariant::Value<'_>> {
+        HashMap::from([
+            ("xesam:title", self.title.as_str().into()),
+            ("xesam:artist", self.artists.clone().into()),
+            ("mpris:length", self.length.into()),
+        ])
+    }
+
+    #[zbus(property)]
+    fn rate(&self) -> f64 {
+        self.rate
+    }
+
+    #[zbus(property(emits_changed_signal = "false"))]
+    fn position(&self) -> i64 {
+        self.position
+    }
+}
+
+/// Returns the player served on `conn`.
+pub async fn player_ref(conn: &Connection) -> InterfaceRef<MockPlayer> {
+    conn.object_server()
+        .interface::<_, MockPlayer>(PLAYER_PATH)
+        .await
+        .unwrap()
+}
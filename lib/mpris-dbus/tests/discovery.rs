@@ -0,0 +1,50 @@
+#![cfg(unix)]
+
+use futures::{Stream, StreamExt};
+use mpris_dbus::{
+    discovery::NameEvent,
+    testing::{Bus, MockPlayer},
+    PlayerRegistry,
+};
+use std::time::Duration;
+use tokio::time::timeout;
+
+const NAME: &str = "org.mpris.MediaPlayer2.mock";
+
+#[tokio::test]
+async fn appearing_and_vanishing_names() {
+    let Some(bus) = Bus::start() else {
+        return;
+    };
+    let player = bus.start_player(NAME, MockPlayer::default()).await;
+
+    let registry = PlayerRegistry::session().await.unwrap();
+    let events = registry.names().await.unwrap();
+    // only look at the mock player (unique names appear as well)
+    let mut events = Box::pin(events.filter(|event| {
+        let (NameEvent::Appeared(name) | NameEvent::Vanished(name)) = event;
+        std::future::ready(name.as_str() == NAME)
+    }));
+
+    assert_eq!(
+        next(&mut events).await,
+        NameEvent::Appeared(NAME.try_into().unwrap())
+    );
+    player.close().await.unwrap();
+    assert_eq!(
+        next(&mut events).await,
+        NameEvent::Vanished(NAME.try_into().unwrap())
+    );
+    let _player = bus.start_player(NAME, MockPlayer::default()).await;
+    assert_eq!(
+        next(&mut events).await,
+        NameEvent::Appeared(NAME.try_into().unwrap())
+    );
+}
+
+async fn next(events: &mut (impl Stream<Item = NameEvent> + Unpin)) -> NameEvent {
+    timeout(Duration::from_secs(5), events.next())
+        .await
+        .expect("No event")
+        .unwrap()
+}
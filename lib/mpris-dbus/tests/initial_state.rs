@@ -0,0 +1,60 @@
+#![cfg(unix)]
+
+use mpris_dbus::{
+    interface::PlaybackStatus,
+    testing::{player_ref, Bus, MockPlayer},
+    PlayerRegistry,
+};
+use std::time::Duration;
+use tokio::time::timeout;
+
+const NAME: &str = "org.mpris.MediaPlayer2.mock";
+
+#[tokio::test]
+async fn initial_state() {
+    let Some(bus) = Bus::start() else {
+        return;
+    };
+    let conn = bus
+        .start_player(
+            NAME,
+            MockPlayer {
+                status: "Playing",
+                title: "Title".to_owned(),
+                artists: vec!["Artist 1".to_owned(), "Artist 2".to_owned()],
+                length: 180_000_000,
+                rate: 1.5,
+                position: 42_000_000,
+            },
+        )
+        .await;
+
+    let registry = PlayerRegistry::session().await.unwrap();
+    let (mut rx, _controls) = registry.listen(NAME).await.unwrap();
+    let state = timeout(Duration::from_secs(5), rx.recv())
+        .await
+        .expect("No initial state")
+        .unwrap();
+    assert_eq!(state.status, PlaybackStatus::Playing);
+    assert_eq!(state.title.as_deref(), Some("Title"));
+    assert_eq!(state.artist, "Artist 1, Artist 2");
+    assert_eq!(state.playback_rate, 1.5);
+    assert_eq!(state.timeline.duration, Some(180_000_000));
+    assert_eq!(state.timeline.position, 42_000_000);
+
+    // changes are still received
+    let player = player_ref(&conn).await;
+    player.get_mut().await.status = "Paused";
+    player
+        .get()
+        .await
+        .playback_status_changed(player.signal_emitter())
+        .await
+        .unwrap();
+    let state = timeout(Duration::from_secs(5), rx.recv())
+        .await
+        .expect("No update")
+        .unwrap();
+    assert_eq!(state.status, PlaybackStatus::Paused);
+    assert_eq!(state.title.as_deref(), Some("Title"));
+}
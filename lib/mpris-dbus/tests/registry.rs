@@ -0,0 +1,76 @@
+#![cfg(unix)]
+
+use futures::StreamExt;
+use mpris_dbus::{
+    discovery::NameEvent,
+    testing::{Bus, MockPlayer},
+    PlayerRegistry,
+};
+use std::time::Duration;
+use tokio::time::timeout;
+
+const NAMES: [&str; 3] = [
+    "org.mpris.MediaPlayer2.first",
+    "org.mpris.MediaPlayer2.second",
+    "org.mpris.MediaPlayer2.third",
+];
+
+/// Counts the connections to the bus (every connection has a unique name).
+async fn connection_count(registry: &PlayerRegistry) -> usize {
+    zbus::fdo::DBusProxy::new(registry.connection())
+        .await
+        .unwrap()
+        .list_names()
+        .await
+        .unwrap()
+        .iter()
+        .filter(|name| name.starts_with(':'))
+        .count()
+}
+
+#[tokio::test]
+async fn shares_one_connection() {
+    let Some(bus) = Bus::start() else {
+        return;
+    };
+    let mut players = Vec::new();
+    for name in NAMES {
+        let player = MockPlayer {
+            status: "Playing",
+            title: name.to_owned(),
+            ..MockPlayer::default()
+        };
+        players.push(bus.start_player(name, player).await);
+    }
+
+    let registry = PlayerRegistry::session().await.unwrap();
+    // the players and the registry
+    assert_eq!(connection_count(&registry).await, NAMES.len() + 1);
+
+    let names = registry.names().await.unwrap();
+    let mut discovered = names
+        .filter_map(|event| async move {
+            match event {
+                NameEvent::Appeared(name) if NAMES.contains(&name.as_str()) => Some(name),
+                _ => None,
+            }
+        })
+        .take(NAMES.len())
+        .collect::<Vec<_>>()
+        .await;
+    discovered.sort();
+    assert_eq!(discovered, NAMES);
+
+    let mut streams = Vec::new();
+    for name in NAMES {
+        let (mut rx, controls) = registry.listen(name).await.unwrap();
+        let state = timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .expect("No initial state")
+            .unwrap();
+        assert_eq!(state.title.as_deref(), Some(name));
+        streams.push((rx, controls));
+    }
+
+    assert_eq!(connection_count(&registry).await, NAMES.len() + 1);
+}
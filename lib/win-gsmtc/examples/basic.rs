@@ -19,6 +19,7 @@ async fn main() -> Result<()> {
                 session_id,
                 mut rx,
                 source,
+                ..
             } => {
                 println!("Created session: {{id={session_id}, source={source}}}");
                 tokio::spawn(async move {
@@ -0,0 +1,106 @@
+use crate::conversion::filetime_to_unix_ms;
+use std::{
+    fmt,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use windows::{
+    core::{AgileReference, Result},
+    Media::Control::{
+        GlobalSystemMediaTransportControlsSession,
+        GlobalSystemMediaTransportControlsSessionPlaybackStatus,
+    },
+};
+
+/// A command for a session. Positions and offsets are in 100ns ticks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Play,
+    Pause,
+    PlayPause,
+    Next,
+    Previous,
+    Seek(i64),
+    SeekTo(i64),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ControlError {
+    /// The session rejected the command.
+    NotSupported,
+    Failed(windows::core::Error),
+}
+
+impl fmt::Display for ControlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotSupported => f.write_str("The session doesn't support this command"),
+            Self::Failed(e) => write!(f, "The command failed: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for ControlError {}
+
+/// Sends commands to a session.
+#[derive(Debug, Clone)]
+pub struct Controls {
+    session: AgileReference<GlobalSystemMediaTransportControlsSession>,
+}
+
+impl Controls {
+    pub(crate) fn new(session: &GlobalSystemMediaTransportControlsSession) -> Result<Self> {
+        Ok(Self {
+            session: AgileReference::new(session)?,
+        })
+    }
+
+    /// Executes `command` on a blocking thread.
+    pub async fn execute(&self, command: Command) -> std::result::Result<(), ControlError> {
+        let session = self.session.clone();
+        let result = tokio::task::spawn_blocking(move || execute_sync(&session, command)).await;
+        match result {
+            Ok(Ok(true)) => Ok(()),
+            Ok(Ok(false)) => Err(ControlError::NotSupported),
+            Ok(Err(e)) => Err(ControlError::Failed(e)),
+            // the task panicked
+            Err(_) => Err(ControlError::Failed(windows::core::Error::empty())),
+        }
+    }
+}
+
+fn execute_sync(
+    session: &AgileReference<GlobalSystemMediaTransportControlsSession>,
+    command: Command,
+) -> Result<bool> {
+    let session = session.resolve()?;
+    let operation = match command {
+        Command::Play => session.TryPlayAsync()?,
+        Command::Pause => session.TryPauseAsync()?,
+        Command::PlayPause => session.TryTogglePlayPauseAsync()?,
+        Command::Next => session.TrySkipNextAsync()?,
+        Command::Previous => session.TrySkipPreviousAsync()?,
+        Command::Seek(offset) => session.TryChangePlaybackPositionAsync(
+            current_position(&session)?.saturating_add(offset).max(0),
+        )?,
+        Command::SeekTo(position) => session.TryChangePlaybackPositionAsync(position.max(0))?,
+    };
+    operation.join()
+}
+
+/// The position in ticks, extrapolated from the last timeline update while playing.
+fn current_position(session: &GlobalSystemMediaTransportControlsSession) -> Result<i64> {
+    let timeline = session.GetTimelineProperties()?;
+    let position = timeline.Position()?.Duration;
+    let playing = session.GetPlaybackInfo()?.PlaybackStatus()?
+        == GlobalSystemMediaTransportControlsSessionPlaybackStatus::Playing;
+    if !playing {
+        return Ok(position);
+    }
+
+    let updated_ms = filetime_to_unix_ms(timeline.LastUpdatedTime()?.UniversalTime);
+    let now_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| i64::try_from(d.as_millis()).unwrap_or(i64::MAX));
+    let elapsed_ms = now_ms.saturating_sub(updated_ms).max(0);
+    Ok(position.saturating_add(elapsed_ms.saturating_mul(10_000)))
+}
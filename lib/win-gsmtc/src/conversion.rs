@@ -155,6 +155,6 @@ impl From<MediaPlaybackAutoRepeatMode> for AutoRepeatMode {
     }
 }
 
-fn filetime_to_unix_ms(filetime: i64) -> i64 {
+pub(crate) fn filetime_to_unix_ms(filetime: i64) -> i64 {
     (filetime / 10000).checked_sub(11644473600000).unwrap_or(0)
 }
@@ -17,6 +17,7 @@
 //!             session_id,
 //!             mut rx,
 //!             source,
+//!             ..
 //!         } => {
 //!             println!("Created session: {{id={session_id}, source={source}}}");
 //!             tokio::spawn(async move {
@@ -44,12 +45,14 @@
 //! # }
 //! ```
 #![cfg(windows)]
+mod controls;
 mod conversion;
 mod manager;
 mod model;
 mod session;
 mod util;
 
+pub use controls::{Command, ControlError, Controls};
 pub use manager::{ManagerEvent, SessionManager};
 pub use model::*;
 pub use session::SessionUpdateEvent;
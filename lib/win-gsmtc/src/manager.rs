@@ -1,4 +1,5 @@
 use crate::{
+    controls::Controls,
     session::{SessionCommand, SessionHandle, SessionUpdateEvent},
     util::ResultExt,
     EventRegistrationToken,
@@ -46,6 +47,8 @@ pub enum ManagerEvent {
         ///
         /// This is the identifier for a session and assumed to be unique per session.
         source: String,
+        /// Sends commands like play or pause to the session.
+        controls: Controls,
     },
     /// Occurs when a previously added session was removed.
     SessionRemoved {
@@ -232,6 +235,7 @@ impl SessionManager {
 
         let (tx, rx) = mpsc::unbounded_channel();
 
+        let controls = Controls::new(&session)?;
         let (session, source) = SessionHandle::create(id, session, tx)?;
         self.sessions.insert(model_id, session);
 
@@ -242,6 +246,7 @@ impl SessionManager {
                 session_id: id,
                 rx,
                 source,
+                controls,
             })
             .ok();
 
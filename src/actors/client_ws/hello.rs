@@ -0,0 +1,78 @@
+use crate::config::{Config, FileOutputMode};
+use chrono::Utc;
+use serde::Serialize;
+
+/// Information about the server sent to clients in the `Hello` message.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerInfo {
+    pub version: &'static str,
+    pub features: Vec<&'static str>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Hello<'a> {
+    #[serde(flatten)]
+    pub info: &'a ServerInfo,
+    pub protocol_version: u32,
+    pub server_time: u64,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeSync {
+    pub server_time: u64,
+    /// The `clientTime` of the request if this is a reply.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_time: Option<u64>,
+}
+
+impl ServerInfo {
+    pub fn from_config(config: &Config) -> Self {
+        let mut features = vec!["images", "ticks", "time-sync", "rest", "events", "sse"];
+        if config.transliteration.enabled {
+            features.push("transliteration");
+        }
+        if config.modules.file.enabled
+            && matches!(
+                config.modules.file.mode,
+                FileOutputMode::NdjsonLog | FileOutputMode::CsvLog
+            )
+        {
+            features.push("history");
+        }
+
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            features,
+        }
+    }
+}
+
+/// The current UTC timestamp in milliseconds.
+pub fn server_time() -> u64 {
+    Utc::now().timestamp_millis().try_into().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hello_json() {
+        let info = ServerInfo {
+            version: "1.2.3",
+            features: vec!["images"],
+        };
+        let hello = Hello {
+            info: &info,
+            protocol_version: 1,
+            server_time: 42,
+        };
+        assert_eq!(
+            serde_json::json!({ "type": "Hello", "data": hello }).to_string(),
+            r#"{"data":{"features":["images"],"protocolVersion":1,"serverTime":42,"version":"1.2.3"},"type":"Hello"}"#
+        );
+    }
+}
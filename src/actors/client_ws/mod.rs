@@ -1,38 +1,131 @@
-use crate::{manager, utilities::websockets::PingingWebsocket};
+mod hello;
+#[cfg(test)]
+mod tests;
+
+use crate::{manager, model::ModuleState, utilities::websockets::PingingWebsocket};
 use actix::{Actor, ActorContext, AsyncContext, StreamHandler};
 use actix_web_actors::{
     ws,
     ws::{Message, ProtocolError},
 };
-use serde::Deserialize;
-use std::time::{Duration, Instant};
+pub use hello::ServerInfo;
+use hello::{server_time, Hello, TimeSync};
+use serde::{Deserialize, Serialize};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use tokio::sync::watch;
 use tokio_stream::wrappers::WatchStream;
 use tracing::{error, event, Level};
 
 const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
 const CLIENT_TIMEOUT: Duration = Duration::from_secs(40);
+const MIN_TICK_INTERVAL: Duration = Duration::from_millis(100);
+const TIME_SYNC_INTERVAL: Duration = Duration::from_secs(10);
 
 pub struct ClientWsSession {
     hb: Instant,
     rx: Option<watch::Receiver<manager::Event>>,
+    info: Arc<ServerInfo>,
+    protocol: Protocol,
+    /// The sequence number of the last event (only set if the protocol is [`Protocol::Events`]).
+    last_seq: Option<u64>,
+
+    /// Interval of `Progress` messages.
+    ticks: Option<Duration>,
+    state: Option<manager::Event>,
+}
+
+/// The messages a client receives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    /// The full state is sent on every update (`/api/ws/client`).
+    Snapshots,
+    /// The full state is sent once, followed by classified events (`/api/ws/client/v2`).
+    Events,
+}
+
+impl Protocol {
+    fn version(self) -> u32 {
+        match self {
+            Protocol::Snapshots => 1,
+            Protocol::Events => 2,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Progress {
+    position_ms: u64,
+    duration_ms: u64,
 }
 
 impl ClientWsSession {
-    pub fn new(rx: watch::Receiver<manager::Event>) -> Self {
+    pub fn new(
+        rx: watch::Receiver<manager::Event>,
+        info: Arc<ServerInfo>,
+        protocol: Protocol,
+    ) -> Self {
         Self {
             hb: Instant::now(),
             rx: Some(rx),
+            info,
+            protocol,
+            last_seq: None,
+            ticks: None,
+            state: None,
         }
     }
+
+    /// Sends the extrapolated progress every `interval` while playing.
+    pub fn with_ticks(mut self, interval: Option<Duration>) -> Self {
+        self.ticks = interval.map(|i| i.max(MIN_TICK_INTERVAL));
+        self
+    }
+
+    fn send_progress(&self, ctx: &mut <Self as Actor>::Context) {
+        let Some(ModuleState::Playing(ref info)) = self.state.as_ref().map(|event| &*event.state)
+        else {
+            return;
+        };
+        let Some(ref timeline) = info.timeline else {
+            return;
+        };
+        let progress = Progress {
+            position_ms: timeline.position_at(server_time()),
+            duration_ms: timeline.duration_ms,
+        };
+        ctx.text(serde_json::json!({ "type": "Progress", "data": progress }).to_string());
+    }
+
+    fn send_time_sync(ctx: &mut <Self as Actor>::Context, client_time: Option<u64>) {
+        let sync = TimeSync {
+            server_time: server_time(),
+            client_time,
+        };
+        ctx.text(serde_json::json!({ "type": "TimeSync", "data": sync }).to_string());
+    }
 }
 
 impl Actor for ClientWsSession {
     type Context = ws::WebsocketContext<Self>;
 
     fn started(&mut self, ctx: &mut Self::Context) {
+        let hello = Hello {
+            info: &self.info,
+            protocol_version: self.protocol.version(),
+            server_time: server_time(),
+        };
+        ctx.text(serde_json::json!({ "type": "Hello", "data": hello }).to_string());
+        ctx.run_interval(TIME_SYNC_INTERVAL, |_, ctx| Self::send_time_sync(ctx, None));
+
         ctx.add_stream(WatchStream::new(self.rx.take().unwrap()));
         self.init_hb_check(ctx, HEARTBEAT_INTERVAL, CLIENT_TIMEOUT);
+        if let Some(interval) = self.ticks {
+            ctx.run_interval(interval, |this, ctx| this.send_progress(ctx));
+        }
     }
 }
 
@@ -44,10 +137,27 @@ impl PingingWebsocket for ClientWsSession {
 
 impl StreamHandler<manager::Event> for ClientWsSession {
     fn handle(&mut self, item: manager::Event, ctx: &mut Self::Context) {
-        match serde_json::to_string(&*item) {
-            Ok(json) => ctx.text(json),
+        let json = match self.protocol {
+            Protocol::Events
+                if self
+                    .last_seq
+                    .replace(item.seq)
+                    .is_some_and(|last| last + 1 == item.seq) =>
+            {
+                item.transitions.iter().map(serde_json::to_string).collect()
+            }
+            // the initial state or updates were coalesced by the channel
+            Protocol::Events | Protocol::Snapshots => {
+                serde_json::to_string(&*item.state).map(|json| vec![json])
+            }
+        };
+        match json {
+            Ok(messages) => messages.into_iter().for_each(|json| ctx.text(json)),
             Err(e) => error!(error=%e, "Cannot serialize json"),
         }
+        if self.ticks.is_some() {
+            self.state = Some(item);
+        }
     }
 
     fn finished(&mut self, ctx: &mut Self::Context) {
@@ -61,12 +171,13 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for ClientWsSession {
             Ok(ws::Message::Ping(msg)) => {
                 ctx.pong(&msg);
             }
-            Ok(ws::Message::Text(text)) => {
-                let msg = serde_json::from_str::<Response>(&text);
-                if let Ok(Response::Pong) = msg {
-                    self.hb = Instant::now();
+            Ok(ws::Message::Text(text)) => match serde_json::from_str::<Response>(&text) {
+                Ok(Response::Pong) => self.hb = Instant::now(),
+                Ok(Response::TimeSync(request)) => {
+                    Self::send_time_sync(ctx, Some(request.client_time));
                 }
-            }
+                Err(_) => (),
+            },
             Ok(_) => (),
             Err(e) => {
                 event!(Level::WARN, error = %e, "WebSocket error");
@@ -80,4 +191,11 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for ClientWsSession {
 #[serde(tag = "type", content = "data")]
 enum Response {
     Pong,
+    TimeSync(TimeSyncRequest),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TimeSyncRequest {
+    client_time: u64,
 }
@@ -0,0 +1,103 @@
+use super::*;
+use crate::{
+    manager::{EventKind, Update},
+    model::PlayInfo,
+};
+use actix_codec::Decoder;
+use actix_http::ws::{Codec, Frame};
+use actix_web::{error::PayloadError, web::Bytes, web::BytesMut};
+use futures::StreamExt;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+
+/// The client's side of a session.
+struct Client {
+    // closes the connection when dropped
+    _tx: mpsc::UnboundedSender<Result<Bytes, PayloadError>>,
+    rx: mpsc::UnboundedReceiver<Bytes>,
+    codec: Codec,
+    buf: BytesMut,
+}
+
+impl Client {
+    fn start(session: ClientWsSession) -> Self {
+        let (tx, input) = mpsc::unbounded_channel();
+        let (output_tx, rx) = mpsc::unbounded_channel();
+        let mut output = ws::WebsocketContext::create(session, UnboundedReceiverStream::new(input))
+            .boxed_local();
+        actix::spawn(async move {
+            while let Some(Ok(bytes)) = output.next().await {
+                if output_tx.send(bytes).is_err() {
+                    break;
+                }
+            }
+        });
+        Self {
+            _tx: tx,
+            rx,
+            codec: Codec::new().client_mode(),
+            buf: BytesMut::new(),
+        }
+    }
+
+    /// Receives the next message with the state or an event.
+    async fn recv(&mut self) -> serde_json::Value {
+        loop {
+            while let Some(frame) = self.codec.decode(&mut self.buf).unwrap() {
+                if let Frame::Text(text) = frame {
+                    let json: serde_json::Value = serde_json::from_slice(&text).unwrap();
+                    if !matches!(json["type"].as_str(), Some("Hello" | "TimeSync" | "Ping")) {
+                        return json;
+                    }
+                }
+            }
+            let bytes = self.rx.recv().await.unwrap();
+            self.buf.extend_from_slice(&bytes);
+        }
+    }
+}
+
+fn server_info() -> Arc<ServerInfo> {
+    Arc::new(ServerInfo {
+        version: "test",
+        features: Vec::new(),
+    })
+}
+
+#[actix::test]
+async fn sends_transitions() {
+    let (tx, rx) = watch::channel(Update::initial());
+    let mut client = Client::start(ClientWsSession::new(rx, server_info(), Protocol::Events));
+    assert_eq!(client.recv().await["type"], "Paused");
+
+    let info = PlayInfo::simple("Title", "Artist", "test");
+    tx.send(Arc::new(Update {
+        seq: 1,
+        state: Arc::new(ModuleState::Playing(info.clone())),
+        transitions: vec![EventKind::Resumed(None), EventKind::TrackChanged(info)],
+    }))
+    .unwrap();
+    assert_eq!(client.recv().await["type"], "Resumed");
+    assert_eq!(client.recv().await["type"], "TrackChanged");
+
+    // the session missed the update with seq 2, so it sends the full state
+    tx.send(Arc::new(Update {
+        seq: 3,
+        state: Arc::new(ModuleState::Paused),
+        transitions: vec![EventKind::Paused],
+    }))
+    .unwrap();
+    let state = client.recv().await;
+    assert_eq!(state["type"], "Paused");
+    assert!(state.get("data").is_none(), "{state}");
+
+    tx.send(Arc::new(Update {
+        seq: 4,
+        state: Arc::new(ModuleState::Playing(PlayInfo::simple(
+            "Title", "Artist", "test",
+        ))),
+        transitions: vec![EventKind::Resumed(None)],
+    }))
+    .unwrap();
+    assert_eq!(client.recv().await["type"], "Resumed");
+}
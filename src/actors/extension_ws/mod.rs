@@ -1,52 +1,183 @@
+mod protocol;
+mod resume;
+#[cfg(test)]
+mod tests;
+
+pub use resume::ResumeStore;
+
 use crate::{
-    actors::manager::{self, Manager},
-    model::PlayInfo,
+    actors::manager::{self, ControlAction, ControlError, Controller, Manager},
+    image_store::{ImageStore, SlotRef},
+    model::{ImageInfo, InternalImage, PlayInfo},
     utilities::websockets::PingingWebsocket,
 };
 use actix::{
-    fut::ready, Actor, ActorContext, ActorFutureExt, Addr, ContextFutureSpawner, Running,
-    StreamHandler, WrapFuture,
+    fut::ready, Actor, ActorContext, ActorFutureExt, Addr, AsyncContext, ContextFutureSpawner,
+    Handler, Message as ActixMessage, Running, StreamHandler, WrapFuture,
 };
+use actix_web::web::Bytes;
 use actix_web_actors::{
     ws,
     ws::{Message, ProtocolError},
 };
-use serde::Deserialize;
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
 use std::{
-    sync::Arc,
+    collections::HashMap,
+    sync::{Arc, RwLock},
     time::{Duration, Instant},
 };
+use tokio::sync::oneshot;
 use tracing::{event, Level};
 
 const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
 const CLIENT_TIMEOUT: Duration = Duration::from_secs(40);
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(5);
+/// The priority of tab modules.
+const TAB_PRIORITY: u8 = 1;
+/// The priority of the tab the extension would show itself.
+const PREFERRED_TAB_PRIORITY: u8 = 2;
+/// The maximum size of a message (covers are sent as a single binary message).
+pub const MAX_FRAME_SIZE: usize = 4 * 1024 * 1024;
+
+/// Identifies a module within a session. Messages without a tab use `None`.
+type TabId = Option<u64>;
+
+/// The module of a tab.
+#[derive(Debug)]
+pub struct TabModule {
+    id: usize,
+    /// The last info of the tab (`None` if it's inactive).
+    info: Option<PlayInfo>,
+    cover: Option<TabCover>,
+}
+
+/// A cover uploaded by the extension.
+#[derive(Debug)]
+struct TabCover {
+    slot: SlotRef,
+    /// The (external) image URL this cover replaces.
+    url: String,
+    image: ImageInfo,
+}
+
+/// Announces a cover that's sent in the next binary message.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct CoverHeader {
+    tab: Option<u64>,
+    url: String,
+    content_type: String,
+}
+
+impl TabModule {
+    fn new(id: usize) -> Self {
+        Self {
+            id,
+            info: None,
+            cover: None,
+        }
+    }
+
+    /// Whether the current image is the uploaded cover.
+    fn uses_cover(&self) -> bool {
+        match (&self.info, &self.cover) {
+            (Some(info), Some(cover)) => {
+                matches!(info.image, Some(ImageInfo::External(ref url)) if *url == cover.url)
+            }
+            _ => false,
+        }
+    }
+
+    fn to_update(&self) -> manager::UpdateModule {
+        let Some(mut info) = self.info.clone() else {
+            return manager::UpdateModule::paused(self.id);
+        };
+        if self.uses_cover() {
+            info.image = self.cover.as_ref().map(|cover| cover.image.clone());
+        }
+        manager::UpdateModule::playing(self.id, info)
+    }
+}
 
 pub struct ExtensionWsSession {
     hb: Instant,
     manager: Arc<Addr<Manager>>,
-    id: usize,
+    resume_store: Arc<Addr<ResumeStore>>,
+    image_store: Arc<RwLock<ImageStore>>,
+    /// Sent to the extension to resume this session after a reconnect.
+    resume_token: String,
+    /// The extension closed the connection, so the modules can be removed immediately.
+    closed: bool,
+    modules: HashMap<TabId, TabModule>,
+    /// The tab the extension would show (sent with `PreferTab`).
+    preferred_tab: Option<u64>,
+    /// The cover that's sent in the next binary message.
+    pending_cover: Option<CoverHeader>,
+
+    command_timeout: Duration,
+    next_command_id: u64,
+    /// Commands waiting for a `ControlAck` or `ControlError`.
+    pending: HashMap<u64, oneshot::Sender<Result<(), ControlError>>>,
 }
 
 impl ExtensionWsSession {
-    pub fn new(manager: Arc<Addr<Manager>>) -> Self {
+    pub fn new(
+        manager: Arc<Addr<Manager>>,
+        resume_store: Arc<Addr<ResumeStore>>,
+        image_store: Arc<RwLock<ImageStore>>,
+    ) -> Self {
         Self {
             hb: Instant::now(),
             manager,
-            id: 0,
+            resume_store,
+            image_store,
+            resume_token: resume::create_token(),
+            closed: false,
+            modules: HashMap::new(),
+            preferred_tab: None,
+            pending_cover: None,
+            command_timeout: COMMAND_TIMEOUT,
+            next_command_id: 0,
+            pending: HashMap::new(),
         }
     }
-}
 
-impl Actor for ExtensionWsSession {
-    type Context = ws::WebsocketContext<Self>;
+    #[cfg(test)]
+    fn with_command_timeout(mut self, timeout: Duration) -> Self {
+        self.command_timeout = timeout;
+        self
+    }
+
+    /// Runs `f` with the module of `tab` and creates the module first if it doesn't exist.
+    ///
+    /// Further messages are only handled after the module was created.
+    fn with_module(
+        &mut self,
+        tab: TabId,
+        ctx: &mut <Self as Actor>::Context,
+        f: impl FnOnce(&mut TabModule, &Self) + 'static,
+    ) {
+        if let Some(mut module) = self.modules.remove(&tab) {
+            f(&mut module, self);
+            self.modules.insert(tab, module);
+            return;
+        }
 
-    fn started(&mut self, ctx: &mut Self::Context) {
         self.manager
-            .send(manager::CreateModule { priority: 1 })
+            .send(manager::CreateModule {
+                priority: self.priority(tab),
+            })
             .into_actor(self)
-            .then(|res: Result<usize, _>, this, ctx| {
+            .then(move |res: Result<usize, _>, this, ctx| {
                 match res {
-                    Ok(id) => this.id = id,
+                    Ok(id) => {
+                        event!(Level::DEBUG, ?tab, id, "Created module");
+                        this.set_controller(tab, id, ctx);
+                        let mut module = TabModule::new(id);
+                        f(&mut module, this);
+                        this.modules.insert(tab, module);
+                    }
                     Err(e) => {
                         event!(Level::WARN, error = %e, "Failed creating module");
                         ctx.stop();
@@ -55,17 +186,310 @@ impl Actor for ExtensionWsSession {
                 ready(())
             })
             .wait(ctx);
+    }
+
+    fn priority(&self, tab: TabId) -> u8 {
+        if tab.is_some() && tab == self.preferred_tab {
+            PREFERRED_TAB_PRIORITY
+        } else {
+            TAB_PRIORITY
+        }
+    }
+
+    /// Shows `tab` before the other tabs of this session.
+    fn prefer(&mut self, tab: Option<u64>) {
+        self.preferred_tab = tab;
+        for (tab, module) in &self.modules {
+            self.manager.do_send(manager::SetPriority {
+                id: module.id,
+                priority: self.priority(*tab),
+            });
+        }
+    }
+
+    fn set_controller(&self, tab: TabId, id: usize, ctx: &mut <Self as Actor>::Context) {
+        self.manager.do_send(manager::SetController {
+            id,
+            controller: Box::new(TabController {
+                session: ctx.address(),
+                tab,
+            }),
+        });
+    }
+
+    /// Reattaches the modules of a previous session.
+    ///
+    /// Modules of tabs that aren't in `tabs` were closed while the connection was lost and are removed.
+    /// Further messages are only handled after the modules were reattached.
+    fn resume(
+        &mut self,
+        token: String,
+        tabs: Option<Vec<u64>>,
+        ctx: &mut <Self as Actor>::Context,
+    ) {
+        self.resume_store
+            .send(resume::Resume { token })
+            .into_actor(self)
+            .then(move |res, this, ctx| {
+                let Some(modules) = res.ok().flatten() else {
+                    event!(Level::DEBUG, "Nothing to resume");
+                    return ready(());
+                };
+                event!(Level::DEBUG, ?modules, "Resumed session");
+                for (tab, module) in modules {
+                    let closed = match (tab, &tabs) {
+                        (Some(tab), Some(tabs)) => !tabs.contains(&tab),
+                        _ => false,
+                    };
+                    // the tab was closed or sent before resuming
+                    if closed || this.modules.contains_key(&tab) {
+                        this.manager
+                            .do_send(manager::RemoveModule { id: module.id });
+                    } else {
+                        this.set_controller(tab, module.id, ctx);
+                        this.manager.do_send(manager::SetPriority {
+                            id: module.id,
+                            priority: this.priority(tab),
+                        });
+                        this.modules.insert(tab, module);
+                    }
+                }
+                ready(())
+            })
+            .wait(ctx);
+    }
+
+    /// Updates the module of `tab` (`None` means the tab is inactive).
+    fn update(&mut self, tab: TabId, info: Option<PlayInfo>, ctx: &mut <Self as Actor>::Context) {
+        self.with_module(tab, ctx, move |module, this| {
+            module.info = info;
+            this.manager.do_send(module.to_update());
+        });
+    }
+
+    /// Stores an uploaded cover in the slot of the tab.
+    fn store_cover(
+        &mut self,
+        cover: CoverHeader,
+        data: Bytes,
+        ctx: &mut <Self as Actor>::Context,
+    ) -> Result<(), protocol::Error> {
+        protocol::validate_cover(&cover.url, &cover.content_type)?;
+
+        self.with_module(cover.tab, ctx, move |module, this| {
+            let slot = match module.cover.take() {
+                Some(old) => old.slot,
+                None => SlotRef::new(&this.image_store),
+            };
+            let epoch_id =
+                this.image_store
+                    .write()
+                    .unwrap()
+                    .store(*slot, cover.content_type, data.to_vec());
+            module.cover = Some(TabCover {
+                image: ImageInfo::Internal(InternalImage {
+                    id: *slot,
+                    epoch_id,
+                }),
+                slot,
+                url: cover.url,
+            });
+            // the info might have been sent before the cover
+            if module.uses_cover() {
+                this.manager.do_send(module.to_update());
+            }
+        });
+        Ok(())
+    }
+
+    fn remove(&mut self, tab: TabId) {
+        if let Some(module) = self.modules.remove(&tab) {
+            event!(Level::DEBUG, ?tab, id = module.id, "Removed module");
+            self.manager
+                .do_send(manager::RemoveModule { id: module.id });
+        }
+    }
+
+    /// Checks the protocol version of the extension.
+    fn handshake(&mut self, version: u32, ctx: &mut <Self as Actor>::Context) {
+        if version == protocol::PROTOCOL_VERSION {
+            return;
+        }
+        event!(Level::WARN, version, "Unsupported extension protocol");
+        Self::reply_error(
+            &protocol::Error::new(
+                protocol::ErrorCode::UnsupportedVersion,
+                format!(
+                    "The server supports protocol version {}",
+                    protocol::PROTOCOL_VERSION
+                ),
+            ),
+            ctx,
+        );
+        self.closed = true;
+        ctx.close(Some(ws::CloseCode::Policy.into()));
+        ctx.stop();
+    }
+
+    fn reply_error(error: &protocol::Error, ctx: &mut <Self as Actor>::Context) {
+        event!(Level::DEBUG, ?error, "Rejected message");
+        ctx.text(serde_json::json!({ "type": "Error", "data": error }).to_string());
+    }
+
+    fn handle_text(
+        &mut self,
+        text: &str,
+        ctx: &mut <Self as Actor>::Context,
+    ) -> Result<(), protocol::Error> {
+        if text.len() > protocol::MAX_TEXT_MESSAGE_SIZE {
+            return Err(protocol::Error::new(
+                protocol::ErrorCode::MessageTooLarge,
+                format!(
+                    "Text messages can't be larger than {} bytes",
+                    protocol::MAX_TEXT_MESSAGE_SIZE
+                ),
+            ));
+        }
+        let msg = serde_json::from_str::<Response>(text).map_err(|e| {
+            protocol::Error::new(protocol::ErrorCode::InvalidMessage, e.to_string())
+        })?;
+        match msg {
+            Response::Pong => self.hb = Instant::now(),
+            Response::Hello { protocol_version } => self.handshake(protocol_version, ctx),
+            Response::Resume { token, tabs } => self.resume(token, tabs, ctx),
+            Response::Cover(cover) => self.pending_cover = Some(cover),
+            Response::Inactive => self.update(None, None, ctx),
+            Response::Active(info) => {
+                protocol::validate_info(&info)?;
+                self.update(None, Some(info), ctx);
+            }
+            Response::TabInactive { tab } => self.update(Some(tab), None, ctx),
+            Response::TabActive { tab, info } => {
+                protocol::validate_info(&info)?;
+                self.update(Some(tab), Some(info), ctx);
+            }
+            Response::TabRemoved { tab } => self.remove(Some(tab)),
+            Response::PreferTab { tab } => self.prefer(tab),
+            Response::ControlAck { id } => self.resolve(id, Ok(())),
+            Response::ControlError {
+                id,
+                unsupported: true,
+                ..
+            } => self.resolve(id, Err(ControlError::Unsupported)),
+            Response::ControlError { id, message, .. } => {
+                self.resolve(id, Err(ControlError::Failed(message)));
+            }
+        }
+        Ok(())
+    }
+
+    fn resolve(&mut self, id: u64, result: Result<(), ControlError>) {
+        if let Some(tx) = self.pending.remove(&id) {
+            tx.send(result).ok();
+        } else {
+            event!(Level::DEBUG, id, "Reply to unknown or expired command");
+        }
+    }
+}
 
+impl Actor for ExtensionWsSession {
+    type Context = ws::WebsocketContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
         self.init_hb_check(ctx, HEARTBEAT_INTERVAL, CLIENT_TIMEOUT);
+        ctx.text(
+            serde_json::json!({
+                "type": "Hello",
+                "data": {
+                    "resumeToken": self.resume_token,
+                    "protocolVersion": protocol::PROTOCOL_VERSION,
+                },
+            })
+            .to_string(),
+        );
     }
 
     fn stopping(&mut self, _: &mut Self::Context) -> Running {
-        self.manager.do_send(manager::RemoveModule { id: self.id });
+        if self.closed || self.modules.is_empty() {
+            for (_, module) in self.modules.drain() {
+                self.manager
+                    .do_send(manager::RemoveModule { id: module.id });
+            }
+        } else {
+            // the connection was lost, the extension might reconnect
+            self.resume_store.do_send(resume::Suspend {
+                token: std::mem::take(&mut self.resume_token),
+                modules: std::mem::take(&mut self.modules),
+            });
+        }
 
         Running::Stop
     }
 }
 
+/// Sends a control action to the extension and returns a receiver for its reply.
+#[derive(ActixMessage)]
+#[rtype(result = "Result<oneshot::Receiver<Result<(), ControlError>>, ControlError>")]
+struct SendControl {
+    tab: TabId,
+    action: ControlAction,
+}
+
+#[derive(Serialize)]
+struct Command {
+    id: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tab: TabId,
+    #[serde(flatten)]
+    action: ControlAction,
+}
+
+impl Handler<SendControl> for ExtensionWsSession {
+    type Result = Result<oneshot::Receiver<Result<(), ControlError>>, ControlError>;
+
+    fn handle(&mut self, msg: SendControl, ctx: &mut Self::Context) -> Self::Result {
+        let id = self.next_command_id;
+        self.next_command_id += 1;
+
+        let command = Command {
+            id,
+            tab: msg.tab,
+            action: msg.action,
+        };
+        ctx.text(serde_json::json!({ "type": "Control", "data": command }).to_string());
+
+        let (tx, rx) = oneshot::channel();
+        self.pending.insert(id, tx);
+        ctx.run_later(self.command_timeout, move |this, _| {
+            if let Some(tx) = this.pending.remove(&id) {
+                event!(Level::DEBUG, id, "Command timed out");
+                tx.send(Err(ControlError::Timeout)).ok();
+            }
+        });
+        Ok(rx)
+    }
+}
+
+/// Controls the module of a tab.
+struct TabController {
+    session: Addr<ExtensionWsSession>,
+    tab: TabId,
+}
+
+impl Controller for TabController {
+    fn control(&self, action: ControlAction) -> BoxFuture<'static, Result<(), ControlError>> {
+        let send = self.session.send(SendControl {
+            tab: self.tab,
+            action,
+        });
+        Box::pin(async move {
+            let disconnected = || ControlError::Failed("The extension disconnected".to_owned());
+            let reply = send.await.map_err(|_| disconnected())??;
+            reply.await.map_err(|_| disconnected())?
+        })
+    }
+}
+
 impl PingingWebsocket for ExtensionWsSession {
     fn last_hb(&self) -> Instant {
         self.hb
@@ -78,25 +502,42 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for ExtensionWsSessio
             Ok(ws::Message::Ping(msg)) => {
                 ctx.pong(&msg);
             }
+            Ok(ws::Message::Binary(data)) => {
+                let result = match self.pending_cover.take() {
+                    Some(cover) => self.store_cover(cover, data, ctx),
+                    None => Err(protocol::Error::new(
+                        protocol::ErrorCode::UnexpectedBinary,
+                        "Binary messages must follow a Cover message",
+                    )),
+                };
+                if let Err(e) = result {
+                    Self::reply_error(&e, ctx);
+                }
+            }
+            Ok(ws::Message::Close(reason)) => {
+                self.closed = true;
+                ctx.close(reason);
+                ctx.stop();
+            }
             Ok(ws::Message::Text(text)) => {
-                let msg = serde_json::from_str::<Response>(&text);
-                match msg {
-                    Ok(Response::Pong) => self.hb = Instant::now(),
-                    Ok(Response::Inactive) => {
-                        self.manager.do_send(manager::UpdateModule::paused(self.id));
-                    }
-                    Ok(Response::Active(info)) => {
-                        self.manager
-                            .do_send(manager::UpdateModule::playing(self.id, info));
-                    }
-                    Err(e) => {
-                        event!(Level::WARN, id = %self.id, error = %e, "Invalid WS message");
-                    }
+                if let Err(e) = self.handle_text(&text, ctx) {
+                    Self::reply_error(&e, ctx);
                 }
             }
             Ok(_) => (),
+            Err(ProtocolError::Overflow) => {
+                Self::reply_error(
+                    &protocol::Error::new(
+                        protocol::ErrorCode::MessageTooLarge,
+                        format!("Messages can't be larger than {MAX_FRAME_SIZE} bytes"),
+                    ),
+                    ctx,
+                );
+                ctx.close(Some(ws::CloseCode::Size.into()));
+                ctx.stop();
+            }
             Err(e) => {
-                event!(Level::WARN, id = %self.id, error = %e, "WebSocket error");
+                event!(Level::WARN, error = %e, "WebSocket error");
                 ctx.stop();
             }
         }
@@ -105,8 +546,51 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for ExtensionWsSessio
 
 #[derive(Deserialize)]
 #[serde(tag = "type", content = "data")]
+#[allow(clippy::large_enum_variant)] // only used for deserialization
 enum Response {
     Pong,
+    /// The first message of the extension.
+    #[serde(rename_all = "camelCase")]
+    Hello {
+        protocol_version: u32,
+    },
+    /// Reattaches the modules of the session that sent `token` in its `Hello`.
+    Resume {
+        token: String,
+        /// The open tabs (if this is `None`, all modules are reattached).
+        #[serde(default)]
+        tabs: Option<Vec<u64>>,
+    },
     Active(PlayInfo),
     Inactive,
+    /// The next binary message is the cover for an image URL.
+    Cover(CoverHeader),
+    /// A tab is playing (tabs are independent modules).
+    TabActive {
+        tab: u64,
+        info: PlayInfo,
+    },
+    TabInactive {
+        tab: u64,
+    },
+    /// A tab was closed or isn't tracked anymore.
+    TabRemoved {
+        tab: u64,
+    },
+    /// The tab the extension would show if only one could be shown (`None` if there's none).
+    PreferTab {
+        tab: Option<u64>,
+    },
+    /// The command `id` was executed.
+    ControlAck {
+        id: u64,
+    },
+    /// The command `id` failed.
+    ControlError {
+        id: u64,
+        #[serde(default)]
+        unsupported: bool,
+        #[serde(default)]
+        message: String,
+    },
 }
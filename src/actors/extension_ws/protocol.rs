@@ -0,0 +1,96 @@
+use crate::model::{ImageInfo, PlayInfo};
+use serde::Serialize;
+
+/// The version of the extension protocol.
+///
+/// Extensions that don't send a `Hello` are assumed to use this version.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// The maximum size of a text message.
+pub const MAX_TEXT_MESSAGE_SIZE: usize = 64 * 1024;
+/// The maximum length (in bytes) of titles, artists, and other text.
+const MAX_TEXT_LENGTH: usize = 1024;
+const MAX_URL_LENGTH: usize = 16 * 1024;
+const MAX_DURATION_MS: u64 = 7 * 24 * 60 * 60 * 1000;
+const MAX_RATE: f32 = 16.0;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ErrorCode {
+    /// The message isn't valid JSON or has an unknown type.
+    InvalidMessage,
+    UnsupportedVersion,
+    MessageTooLarge,
+    /// A value is out of range or too long.
+    InvalidValue,
+    /// A binary message wasn't announced by a `Cover` message.
+    UnexpectedBinary,
+}
+
+/// Sent to the extension as an `Error` message.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+impl Error {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    fn invalid_value(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidValue, message)
+    }
+}
+
+fn check_length(field: &str, value: &str, max: usize) -> Result<(), Error> {
+    if value.len() > max {
+        return Err(Error::invalid_value(format!(
+            "{field} is longer than {max} bytes"
+        )));
+    }
+    Ok(())
+}
+
+pub fn validate_info(info: &PlayInfo) -> Result<(), Error> {
+    check_length("title", &info.title, MAX_TEXT_LENGTH)?;
+    check_length("artist", &info.artist, MAX_TEXT_LENGTH)?;
+    if let Some(ref album) = info.album {
+        check_length("album.title", &album.title, MAX_TEXT_LENGTH)?;
+    }
+    match info.image {
+        Some(ImageInfo::External(ref url)) => check_length("image", url, MAX_URL_LENGTH)?,
+        Some(ImageInfo::Internal(_)) => {
+            return Err(Error::invalid_value(
+                "image must be a URL (upload covers with a Cover message)",
+            ))
+        }
+        None => (),
+    }
+    if let Some(ref timeline) = info.timeline {
+        if timeline.duration_ms > MAX_DURATION_MS || timeline.progress_ms > MAX_DURATION_MS {
+            return Err(Error::invalid_value(format!(
+                "timeline.durationMs and timeline.progressMs must be at most {MAX_DURATION_MS}"
+            )));
+        }
+        if !(0.0..=MAX_RATE).contains(&timeline.rate) {
+            return Err(Error::invalid_value(format!(
+                "timeline.rate must be between 0 and {MAX_RATE}"
+            )));
+        }
+    }
+    Ok(())
+}
+
+pub fn validate_cover(url: &str, content_type: &str) -> Result<(), Error> {
+    check_length("url", url, MAX_URL_LENGTH)?;
+    check_length("contentType", content_type, MAX_TEXT_LENGTH)?;
+    if !content_type.starts_with("image/") {
+        return Err(Error::invalid_value("contentType must be an image type"));
+    }
+    Ok(())
+}
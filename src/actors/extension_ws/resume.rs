@@ -0,0 +1,103 @@
+use super::{TabId, TabModule};
+use crate::actors::manager::{self, Manager};
+use actix::{Actor, Addr, AsyncContext, Context, Handler, Message, SpawnHandle};
+use std::{collections::HashMap, fmt::Write, time::Duration};
+use tracing::{event, Level};
+
+/// How long the modules of a disconnected extension are kept.
+const GRACE_PERIOD: Duration = Duration::from_secs(15);
+
+/// Keeps the modules of disconnected extensions, so they can be reattached by a new session.
+pub struct ResumeStore {
+    manager: Addr<Manager>,
+    grace_period: Duration,
+
+    suspended: HashMap<String, Suspended>,
+}
+
+struct Suspended {
+    modules: HashMap<TabId, TabModule>,
+    expiry: SpawnHandle,
+}
+
+/// Keeps the modules of a session until they're resumed with `token` or the grace period ends.
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct Suspend {
+    pub token: String,
+    pub modules: HashMap<TabId, TabModule>,
+}
+
+/// Takes the modules suspended with `token`.
+#[derive(Message)]
+#[rtype(result = "Option<HashMap<TabId, TabModule>>")]
+pub struct Resume {
+    pub token: String,
+}
+
+impl ResumeStore {
+    pub fn new(manager: Addr<Manager>) -> Self {
+        Self {
+            manager,
+            grace_period: GRACE_PERIOD,
+            suspended: HashMap::new(),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn with_grace_period(mut self, grace_period: Duration) -> Self {
+        self.grace_period = grace_period;
+        self
+    }
+}
+
+/// Creates a random token to resume a session.
+pub fn create_token() -> String {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes).expect("No random source available");
+    bytes
+        .iter()
+        .fold(String::with_capacity(32), |mut token, b| {
+            write!(token, "{b:02x}").unwrap();
+            token
+        })
+}
+
+impl Actor for ResumeStore {
+    type Context = Context<Self>;
+}
+
+impl Handler<Suspend> for ResumeStore {
+    type Result = ();
+
+    fn handle(&mut self, msg: Suspend, ctx: &mut Self::Context) -> Self::Result {
+        let token = msg.token.clone();
+        let expiry = ctx.run_later(self.grace_period, move |this, _| {
+            let Some(suspended) = this.suspended.remove(&token) else {
+                return;
+            };
+            event!(Level::DEBUG, modules = ?suspended.modules, "Suspended session expired");
+            for module in suspended.modules.into_values() {
+                this.manager
+                    .do_send(manager::RemoveModule { id: module.id });
+            }
+        });
+        self.suspended.insert(
+            msg.token,
+            Suspended {
+                modules: msg.modules,
+                expiry,
+            },
+        );
+    }
+}
+
+impl Handler<Resume> for ResumeStore {
+    type Result = Option<HashMap<TabId, TabModule>>;
+
+    fn handle(&mut self, msg: Resume, ctx: &mut Self::Context) -> Self::Result {
+        let suspended = self.suspended.remove(&msg.token)?;
+        ctx.cancel_future(suspended.expiry);
+        Some(suspended.modules)
+    }
+}
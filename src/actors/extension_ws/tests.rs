@@ -0,0 +1,644 @@
+use super::*;
+use crate::{
+    actors::manager::Control,
+    model::{ImageInfo, ModuleState},
+};
+use actix_codec::{Decoder, Encoder};
+use actix_http::ws::{Codec, Frame};
+use actix_web::{
+    error::PayloadError,
+    web::{Bytes, BytesMut},
+};
+use futures::StreamExt;
+use tokio::sync::{mpsc, watch};
+use tokio_stream::wrappers::UnboundedReceiverStream;
+
+/// The extension's side of a session.
+struct Extension {
+    tx: mpsc::UnboundedSender<Result<Bytes, PayloadError>>,
+    rx: mpsc::UnboundedReceiver<Bytes>,
+    codec: Codec,
+    buf: BytesMut,
+}
+
+impl Extension {
+    fn start(session: ExtensionWsSession) -> Self {
+        let (tx, input) = mpsc::unbounded_channel();
+        let (output_tx, rx) = mpsc::unbounded_channel();
+        let mut output = ws::WebsocketContext::with_codec(
+            session,
+            UnboundedReceiverStream::new(input),
+            Codec::new().max_size(MAX_FRAME_SIZE),
+        )
+        .boxed_local();
+        actix::spawn(async move {
+            while let Some(Ok(bytes)) = output.next().await {
+                if output_tx.send(bytes).is_err() {
+                    break;
+                }
+            }
+        });
+        Self {
+            tx,
+            rx,
+            codec: Codec::new().client_mode(),
+            buf: BytesMut::new(),
+        }
+    }
+
+    fn send(&mut self, json: &serde_json::Value) {
+        self.send_text(json.to_string());
+    }
+
+    fn send_binary(&mut self, data: impl Into<Bytes>) {
+        let mut buf = BytesMut::new();
+        self.codec
+            .encode(ws::Message::Binary(data.into()), &mut buf)
+            .unwrap();
+        self.tx.send(Ok(buf.freeze())).unwrap();
+    }
+
+    /// Closes the connection like a browser would.
+    fn close(mut self) {
+        let mut buf = BytesMut::new();
+        self.codec
+            .encode(ws::Message::Close(None), &mut buf)
+            .unwrap();
+        self.tx.send(Ok(buf.freeze())).unwrap();
+    }
+
+    fn send_text(&mut self, text: String) {
+        let mut buf = BytesMut::new();
+        self.codec
+            .encode(ws::Message::Text(text.into()), &mut buf)
+            .unwrap();
+        self.tx.send(Ok(buf.freeze())).unwrap();
+    }
+
+    /// Waits until the session closes the connection.
+    async fn recv_close(&mut self) -> Option<ws::CloseReason> {
+        loop {
+            while let Some(frame) = self.codec.decode(&mut self.buf).unwrap() {
+                if let Frame::Close(reason) = frame {
+                    return reason;
+                }
+            }
+            let bytes = self.rx.recv().await.unwrap();
+            self.buf.extend_from_slice(&bytes);
+        }
+    }
+
+    /// Receives the resume token from the `Hello` message.
+    async fn hello(&mut self) -> String {
+        let hello = self.recv_any().await;
+        assert_eq!(hello["type"], "Hello");
+        hello["data"]["resumeToken"].as_str().unwrap().to_owned()
+    }
+
+    /// Receives the next text message (skipping pings and hellos).
+    async fn recv(&mut self) -> serde_json::Value {
+        loop {
+            let json = self.recv_any().await;
+            if json["type"] != "Hello" {
+                return json;
+            }
+        }
+    }
+
+    async fn recv_any(&mut self) -> serde_json::Value {
+        loop {
+            while let Some(frame) = self.codec.decode(&mut self.buf).unwrap() {
+                if let Frame::Text(text) = frame {
+                    let json: serde_json::Value = serde_json::from_slice(&text).unwrap();
+                    if json["type"] != "Ping" {
+                        return json;
+                    }
+                }
+            }
+            let bytes = self.rx.recv().await.unwrap();
+            self.buf.extend_from_slice(&bytes);
+        }
+    }
+}
+
+fn start_manager() -> Addr<Manager> {
+    let (event_tx, _) = watch::channel(manager::Update::initial());
+    Manager::new(event_tx).start()
+}
+
+fn new_session(manager: &Addr<Manager>) -> ExtensionWsSession {
+    let resume_store = ResumeStore::new(manager.clone()).start();
+    ExtensionWsSession::new(
+        Arc::new(manager.clone()),
+        Arc::new(resume_store),
+        Arc::new(RwLock::new(ImageStore::new())),
+    )
+}
+
+async fn control(manager: &Addr<Manager>, action: ControlAction) -> Result<(), ControlError> {
+    manager.send(Control { id: None, action }).await.unwrap()
+}
+
+/// Makes the session's module the current one.
+async fn activate(extension: &mut Extension, manager: &Addr<Manager>) {
+    extension.send(&serde_json::json!({
+        "type": "Active",
+        "data": { "title": "Title", "artist": "Artist", "trackNumber": null, "image": null, "timeline": null, "album": null }
+    }));
+    // wait until the module is controllable and current
+    for _ in 0..100 {
+        let modules = manager.send(manager::ListModules).await.unwrap();
+        if modules.iter().any(|m| m.current && m.controllable) {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("The session didn't become the current module");
+}
+
+#[actix::test]
+async fn acknowledged_commands() {
+    let manager = start_manager();
+    let mut extension = Extension::start(new_session(&manager));
+    activate(&mut extension, &manager).await;
+
+    let result = tokio::spawn({
+        let manager = manager.clone();
+        async move { control(&manager, ControlAction::SeekTo { position_ms: 1500 }).await }
+    });
+    let command = extension.recv().await;
+    assert_eq!(command["type"], "Control");
+    assert_eq!(command["data"]["action"], "seekTo");
+    assert_eq!(command["data"]["positionMs"], 1500);
+    extension.send(
+        &serde_json::json!({ "type": "ControlAck", "data": { "id": command["data"]["id"] } }),
+    );
+    assert_eq!(result.await.unwrap(), Ok(()));
+
+    let result = tokio::spawn({
+        let manager = manager.clone();
+        async move { control(&manager, ControlAction::Next).await }
+    });
+    let command = extension.recv().await;
+    assert_eq!(command["data"]["action"], "next");
+    extension.send(&serde_json::json!({
+        "type": "ControlError",
+        "data": { "id": command["data"]["id"], "unsupported": true, "message": "No next track" }
+    }));
+    assert_eq!(result.await.unwrap(), Err(ControlError::Unsupported));
+}
+
+#[actix::test]
+async fn commands_time_out() {
+    let manager = start_manager();
+    let session = new_session(&manager).with_command_timeout(Duration::from_millis(50));
+    let mut extension = Extension::start(session);
+    activate(&mut extension, &manager).await;
+
+    let result = tokio::spawn({
+        let manager = manager.clone();
+        async move { control(&manager, ControlAction::Pause).await }
+    });
+    let command = extension.recv().await;
+    assert_eq!(result.await.unwrap(), Err(ControlError::Timeout));
+
+    // late replies are ignored
+    extension.send(
+        &serde_json::json!({ "type": "ControlAck", "data": { "id": command["data"]["id"] } }),
+    );
+    let result = tokio::spawn({
+        let manager = manager.clone();
+        async move { control(&manager, ControlAction::Play).await }
+    });
+    let command = extension.recv().await;
+    assert_eq!(command["data"]["action"], "play");
+    extension.send(
+        &serde_json::json!({ "type": "ControlAck", "data": { "id": command["data"]["id"] } }),
+    );
+    assert_eq!(result.await.unwrap(), Ok(()));
+}
+
+async fn wait_for_modules(manager: &Addr<Manager>, count: usize) -> Vec<manager::ModuleInfo> {
+    for _ in 0..100 {
+        let modules = manager.send(manager::ListModules).await.unwrap();
+        if modules.len() == count {
+            return modules;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("Expected {count} modules");
+}
+
+fn tab_active(tab: u64, title: &str) -> serde_json::Value {
+    tab_active_with_image(tab, title, None)
+}
+
+fn tab_active_with_image(tab: u64, title: &str, image: Option<&str>) -> serde_json::Value {
+    serde_json::json!({
+        "type": "TabActive",
+        "data": {
+            "tab": tab,
+            "info": { "title": title, "artist": "Artist", "trackNumber": null, "image": image, "timeline": null, "album": null }
+        }
+    })
+}
+
+#[actix::test]
+async fn per_tab_modules() {
+    let manager = start_manager();
+    let mut extension = Extension::start(new_session(&manager));
+
+    extension.send(&tab_active(1, "One"));
+    extension.send(&tab_active(2, "Two"));
+    let modules = wait_for_modules(&manager, 2).await;
+    assert!(modules
+        .iter()
+        .all(|m| m.controllable && matches!(m.state, ModuleState::Playing(_))));
+
+    // commands are sent to the tab of the module
+    let tab_two = modules
+        .iter()
+        .find(|m| matches!(m.state, ModuleState::Playing(ref info) if info.title == "Two"))
+        .unwrap()
+        .id;
+    let result = tokio::spawn({
+        let manager = manager.clone();
+        async move {
+            manager
+                .send(Control {
+                    id: Some(tab_two),
+                    action: ControlAction::Pause,
+                })
+                .await
+                .unwrap()
+        }
+    });
+    let command = extension.recv().await;
+    assert_eq!(command["data"]["tab"], 2);
+    extension.send(
+        &serde_json::json!({ "type": "ControlAck", "data": { "id": command["data"]["id"] } }),
+    );
+    assert_eq!(result.await.unwrap(), Ok(()));
+
+    extension.send(&serde_json::json!({ "type": "TabInactive", "data": { "tab": 2 } }));
+    extension.send(&serde_json::json!({ "type": "TabRemoved", "data": { "tab": 1 } }));
+    let modules = wait_for_modules(&manager, 1).await;
+    assert_eq!(modules[0].id, tab_two);
+
+    // closing the socket removes all modules
+    extension.send(&tab_active(3, "Three"));
+    wait_for_modules(&manager, 2).await;
+    extension.close();
+    wait_for_modules(&manager, 0).await;
+}
+
+/// Waits until the current module plays `title`.
+async fn wait_for_current(manager: &Addr<Manager>, title: &str) {
+    for _ in 0..100 {
+        let modules = manager.send(manager::ListModules).await.unwrap();
+        if modules.iter().any(|m| {
+            m.current && matches!(m.state, ModuleState::Playing(ref info) if info.title == title)
+        }) {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("The current module didn't play {title}");
+}
+
+#[actix::test]
+async fn preferred_tabs() {
+    let manager = start_manager();
+    let mut extension = Extension::start(new_session(&manager));
+    let prefer =
+        |tab: Option<u64>| serde_json::json!({ "type": "PreferTab", "data": { "tab": tab } });
+
+    extension.send(&tab_active(1, "One"));
+    extension.send(&prefer(Some(1)));
+    extension.send(&tab_active(2, "Two"));
+    wait_for_modules(&manager, 2).await;
+    wait_for_current(&manager, "One").await;
+
+    extension.send(&prefer(Some(2)));
+    wait_for_current(&manager, "Two").await;
+    // updates of other tabs don't change the shown tab
+    extension.send(&tab_active(1, "One"));
+    extension.send(&tab_active(2, "Two"));
+    wait_for_current(&manager, "Two").await;
+
+    extension.send(&prefer(Some(1)));
+    wait_for_current(&manager, "One").await;
+    extension.send(&prefer(None));
+    for _ in 0..100 {
+        let modules = manager.send(manager::ListModules).await.unwrap();
+        if modules.iter().all(|m| m.priority == TAB_PRIORITY) {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("The tab is still preferred");
+}
+
+#[actix::test]
+async fn resumed_sessions() {
+    let manager = start_manager();
+    let resume_store = Arc::new(
+        ResumeStore::new(manager.clone())
+            .with_grace_period(Duration::from_millis(200))
+            .start(),
+    );
+    let image_store = Arc::new(RwLock::new(ImageStore::new()));
+    let connect = || {
+        Extension::start(ExtensionWsSession::new(
+            Arc::new(manager.clone()),
+            resume_store.clone(),
+            image_store.clone(),
+        ))
+    };
+
+    let mut extension = connect();
+    let token = extension.hello().await;
+    extension.send(&tab_active(1, "One"));
+    let id = wait_for_modules(&manager, 1).await[0].id;
+    extension.send(&tab_active(3, "Three"));
+    let closed_id = wait_for_modules(&manager, 2)
+        .await
+        .iter()
+        .find(|m| m.id != id)
+        .unwrap()
+        .id;
+
+    // the connection is lost and the modules keep their state
+    drop(extension);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let modules = wait_for_modules(&manager, 2).await;
+    assert!(modules
+        .iter()
+        .all(|m| matches!(m.state, ModuleState::Playing(_))));
+
+    // tab 3 was closed in the meantime
+    let mut extension = connect();
+    let new_token = extension.hello().await;
+    assert_ne!(token, new_token);
+    extension
+        .send(&serde_json::json!({ "type": "Resume", "data": { "token": token, "tabs": [1, 2] } }));
+    extension.send(&tab_active(1, "One"));
+    extension.send(&tab_active(2, "Two"));
+    let mut resumed = false;
+    for _ in 0..100 {
+        let modules = manager.send(manager::ListModules).await.unwrap();
+        let ids: Vec<usize> = modules.iter().map(|m| m.id).collect();
+        if ids.len() == 2 && ids.contains(&id) && !ids.contains(&closed_id) {
+            resumed = true;
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert!(resumed, "the closed tab was removed");
+
+    // the module is controlled through the new session
+    let result = tokio::spawn({
+        let manager = manager.clone();
+        async move {
+            manager
+                .send(Control {
+                    id: Some(id),
+                    action: ControlAction::Pause,
+                })
+                .await
+                .unwrap()
+        }
+    });
+    let command = extension.recv().await;
+    assert_eq!(command["data"]["tab"], 1);
+    extension.send(
+        &serde_json::json!({ "type": "ControlAck", "data": { "id": command["data"]["id"] } }),
+    );
+    assert_eq!(result.await.unwrap(), Ok(()));
+
+    // the modules are removed after the grace period
+    drop(extension);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    wait_for_modules(&manager, 2).await;
+    wait_for_modules(&manager, 0).await;
+
+    // expired tokens are ignored
+    let mut extension = connect();
+    extension.hello().await;
+    extension.send(&serde_json::json!({ "type": "Resume", "data": { "token": new_token } }));
+    extension.send(&tab_active(1, "One"));
+    assert_ne!(wait_for_modules(&manager, 1).await[0].id, id);
+}
+
+/// Waits until the only module plays `title` and returns its image.
+async fn wait_for_track(manager: &Addr<Manager>, title: &str) -> Option<ImageInfo> {
+    for _ in 0..100 {
+        let modules = manager.send(manager::ListModules).await.unwrap();
+        if let [manager::ModuleInfo {
+            state: ModuleState::Playing(info),
+            ..
+        }] = modules.as_slice()
+        {
+            if info.title == title {
+                return info.image.clone();
+            }
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("The module didn't play {title}");
+}
+
+#[actix::test]
+async fn uploaded_covers() {
+    const BLOB: &str = "blob:https://example.com/cover";
+
+    let manager = start_manager();
+    let image_store = Arc::new(RwLock::new(ImageStore::new()));
+    let mut extension = Extension::start(ExtensionWsSession::new(
+        Arc::new(manager.clone()),
+        Arc::new(ResumeStore::new(manager.clone()).start()),
+        image_store.clone(),
+    ));
+    let upload = |extension: &mut Extension, content_type: &str| {
+        extension.send(&serde_json::json!({
+            "type": "Cover",
+            "data": { "tab": 1, "url": BLOB, "contentType": content_type }
+        }));
+        extension.send_binary(vec![1, 2, 3]);
+    };
+
+    // the cover is uploaded after the info
+    extension.send(&tab_active_with_image(1, "One", Some(BLOB)));
+    assert_eq!(
+        wait_for_track(&manager, "One").await,
+        Some(ImageInfo::External(BLOB.to_owned()))
+    );
+    upload(&mut extension, "image/png");
+    let mut image = None;
+    for _ in 0..100 {
+        if let Some(ImageInfo::Internal(internal)) = wait_for_track(&manager, "One").await {
+            image = Some(internal);
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    let image = image.expect("The cover wasn't used");
+    {
+        let store = image_store.read().unwrap();
+        let stored = store.get(image.id, image.epoch_id).unwrap();
+        assert_eq!(stored.content_type, "image/png");
+        assert_eq!(stored.data, [1, 2, 3]);
+    }
+
+    // the cover is kept for later updates with the same URL
+    extension.send(&tab_active_with_image(1, "Two", Some(BLOB)));
+    assert_eq!(
+        wait_for_track(&manager, "Two").await,
+        Some(ImageInfo::Internal(image.clone()))
+    );
+    let other = "https://example.com/other";
+    extension.send(&tab_active_with_image(1, "Three", Some(other)));
+    assert_eq!(
+        wait_for_track(&manager, "Three").await,
+        Some(ImageInfo::External(other.to_owned()))
+    );
+
+    // other content types are rejected
+    upload(&mut extension, "text/html");
+    expect_error(&mut extension, "invalidValue").await;
+    extension.send(&tab_active_with_image(1, "Four", Some(BLOB)));
+    assert_eq!(
+        wait_for_track(&manager, "Four").await,
+        Some(ImageInfo::Internal(image.clone()))
+    );
+
+    // the slot is freed with the module
+    extension.send(&serde_json::json!({ "type": "TabRemoved", "data": { "tab": 1 } }));
+    wait_for_modules(&manager, 0).await;
+    assert!(image_store
+        .read()
+        .unwrap()
+        .get(image.id, image.epoch_id)
+        .is_none());
+}
+
+async fn expect_error(extension: &mut Extension, code: &str) {
+    let msg = extension.recv().await;
+    assert_eq!(msg["type"], "Error", "{msg}");
+    assert_eq!(msg["data"]["code"], code, "{msg}");
+}
+
+#[actix::test]
+async fn version_handshake() {
+    let manager = start_manager();
+    let mut extension = Extension::start(new_session(&manager));
+    let hello = extension.recv_any().await;
+    assert_eq!(hello["data"]["protocolVersion"], 1);
+
+    extension.send(&serde_json::json!({ "type": "Hello", "data": { "protocolVersion": 1 } }));
+    extension.send(&tab_active(1, "One"));
+    wait_for_modules(&manager, 1).await;
+
+    let mut extension = Extension::start(new_session(&manager));
+    extension.send(&serde_json::json!({ "type": "Hello", "data": { "protocolVersion": 2 } }));
+    expect_error(&mut extension, "unsupportedVersion").await;
+    let reason = extension.recv_close().await.unwrap();
+    assert_eq!(reason.code, ws::CloseCode::Policy);
+}
+
+#[actix::test]
+async fn rejects_malformed_messages() {
+    let manager = start_manager();
+    let mut extension = Extension::start(new_session(&manager));
+
+    extension.send_text("{not json".to_owned());
+    expect_error(&mut extension, "invalidMessage").await;
+    extension.send(&serde_json::json!({ "type": "Unknown" }));
+    expect_error(&mut extension, "invalidMessage").await;
+    extension.send(&serde_json::json!({ "type": "TabActive", "data": { "tab": 1 } }));
+    expect_error(&mut extension, "invalidMessage").await;
+
+    // the session is still usable
+    extension.send(&tab_active(1, "One"));
+    wait_for_modules(&manager, 1).await;
+}
+
+#[actix::test]
+async fn rejects_large_messages() {
+    let manager = start_manager();
+    let mut extension = Extension::start(new_session(&manager));
+
+    let padding = "a".repeat(protocol::MAX_TEXT_MESSAGE_SIZE);
+    extension.send(&serde_json::json!({ "type": "Pong", "data": padding }));
+    expect_error(&mut extension, "messageTooLarge").await;
+
+    extension.send(&serde_json::json!({
+        "type": "Cover",
+        "data": { "tab": 1, "url": "blob:cover", "contentType": "image/png" }
+    }));
+    extension.send_binary(vec![0; MAX_FRAME_SIZE + 1]);
+    expect_error(&mut extension, "messageTooLarge").await;
+    let reason = extension.recv_close().await.unwrap();
+    assert_eq!(reason.code, ws::CloseCode::Size);
+}
+
+#[actix::test]
+async fn rejects_long_strings() {
+    let manager = start_manager();
+    let mut extension = Extension::start(new_session(&manager));
+
+    extension.send(&tab_active(1, &"a".repeat(2000)));
+    expect_error(&mut extension, "invalidValue").await;
+    let mut message = tab_active(1, "One");
+    message["data"]["info"]["album"] =
+        serde_json::json!({ "title": "a".repeat(2000), "trackCount": 1 });
+    extension.send(&message);
+    expect_error(&mut extension, "invalidValue").await;
+    extension.send(&tab_active_with_image(1, "One", Some(&"a".repeat(20_000))));
+    expect_error(&mut extension, "invalidValue").await;
+
+    // no module is created for rejected messages
+    extension.send(&tab_active(2, "Two"));
+    let modules = wait_for_modules(&manager, 1).await;
+    assert!(matches!(modules[0].state, ModuleState::Playing(ref info) if info.title == "Two"));
+}
+
+#[actix::test]
+async fn rejects_out_of_range_timelines() {
+    let manager = start_manager();
+    let mut extension = Extension::start(new_session(&manager));
+    let with_timeline = |duration_ms: u64, rate: f32| {
+        let mut message = tab_active(1, "One");
+        message["data"]["info"]["timeline"] = serde_json::json!({
+            "ts": 0, "durationMs": duration_ms, "progressMs": 0, "rate": rate
+        });
+        message
+    };
+
+    extension.send(&with_timeline(u64::MAX / 2, 1.0));
+    expect_error(&mut extension, "invalidValue").await;
+    extension.send(&with_timeline(60_000, -1.0));
+    expect_error(&mut extension, "invalidValue").await;
+    extension.send(&with_timeline(60_000, 1000.0));
+    expect_error(&mut extension, "invalidValue").await;
+
+    extension.send(&with_timeline(60_000, 1.0));
+    wait_for_modules(&manager, 1).await;
+}
+
+#[actix::test]
+async fn rejects_unexpected_binary() {
+    let manager = start_manager();
+    let mut extension = Extension::start(new_session(&manager));
+
+    extension.send_binary(vec![1, 2, 3]);
+    expect_error(&mut extension, "unexpectedBinary").await;
+    // a cover is only used for the next binary message
+    extension.send(&serde_json::json!({
+        "type": "Cover",
+        "data": { "tab": 1, "url": "blob:cover", "contentType": "image/png" }
+    }));
+    extension.send_binary(vec![1, 2, 3]);
+    extension.send_binary(vec![1, 2, 3]);
+    expect_error(&mut extension, "unexpectedBinary").await;
+}
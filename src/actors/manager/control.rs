@@ -0,0 +1,50 @@
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "camelCase")]
+pub enum ControlAction {
+    Play,
+    Pause,
+    Toggle,
+    Next,
+    Previous,
+    /// Seeks relative to the current position.
+    Seek {
+        #[serde(rename = "offsetMs")]
+        offset_ms: i64,
+    },
+    /// Seeks to an absolute position.
+    SeekTo {
+        #[serde(rename = "positionMs")]
+        position_ms: u64,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ControlError {
+    #[error("No module to control")]
+    NoModule,
+    #[error("Module {0} doesn't exist")]
+    UnknownModule(usize),
+    #[error("The module doesn't support this action")]
+    Unsupported,
+    #[error("The player failed: {0}")]
+    Failed(String),
+    #[error("The player didn't respond in time")]
+    Timeout,
+}
+
+/// Executes control actions for a module.
+///
+/// Modules register their controller with [`SetController`](super::SetController).
+pub trait Controller: Send {
+    fn control(&self, action: ControlAction) -> BoxFuture<'static, Result<(), ControlError>>;
+}
+
+impl fmt::Debug for dyn Controller {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Controller")
+    }
+}
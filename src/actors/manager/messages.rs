@@ -1,5 +1,7 @@
+use super::{ControlAction, ControlError, Controller};
 use crate::model::{ModuleState, PlayInfo};
 use actix::Message;
+use serde::Serialize;
 
 type Unit = ();
 
@@ -37,3 +39,39 @@ impl UpdateModule {
 pub struct RemoveModule {
     pub id: usize,
 }
+
+/// Changes the priority of an existing module.
+#[derive(Message)]
+#[rtype(Unit)]
+pub struct SetPriority {
+    pub id: usize,
+    pub priority: u8,
+}
+
+#[derive(Message)]
+#[rtype(Unit)]
+pub struct SetController {
+    pub id: usize,
+    pub controller: Box<dyn Controller>,
+}
+
+/// Controls the module `id` or the current (or last active) one.
+#[derive(Message)]
+#[rtype(result = "Result<(), ControlError>")]
+pub struct Control {
+    pub id: Option<usize>,
+    pub action: ControlAction,
+}
+
+#[derive(Message)]
+#[rtype(result = "Vec<ModuleInfo>")]
+pub struct ListModules;
+
+#[derive(Debug, Serialize)]
+pub struct ModuleInfo {
+    pub id: usize,
+    pub priority: u8,
+    pub current: bool,
+    pub controllable: bool,
+    pub state: ModuleState,
+}
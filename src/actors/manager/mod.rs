@@ -1,20 +1,58 @@
+mod control;
 mod messages;
 #[cfg(test)]
 mod tests;
+mod transitions;
 
-use crate::model::ModuleState;
-use actix::{Actor, Context, Handler};
+use crate::{config::TransliterationMode, model::ModuleState, transliteration};
+use actix::{Actor, Context, Handler, ResponseFuture};
+pub use control::*;
 pub use messages::*;
 use std::{collections::HashMap, sync::Arc};
 use tokio::sync::watch;
 use tracing::{error, event, Level};
+use transitions::Classifier;
+pub use transitions::EventKind;
 
-pub type Event = Arc<ModuleState>;
+pub type Event = Arc<Update>;
+
+/// A state published by the manager.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Update {
+    /// Increases by one with every published state, so consumers can tell if they missed one.
+    pub seq: u64,
+    pub state: Arc<ModuleState>,
+    /// The transitions from the previously published state.
+    pub transitions: Vec<EventKind>,
+}
+
+impl Update {
+    /// The event before anything was published.
+    pub fn initial() -> Event {
+        Arc::new(Self {
+            seq: 0,
+            state: Arc::new(ModuleState::Paused),
+            transitions: Vec::new(),
+        })
+    }
+
+    #[cfg(test)]
+    pub fn with_state(seq: u64, state: ModuleState) -> Event {
+        Arc::new(Self {
+            seq,
+            state: Arc::new(state),
+            transitions: Vec::new(),
+        })
+    }
+}
 
 #[derive(Debug)]
 struct Module {
     priority: u8,
     state: Arc<ModuleState>,
+    controller: Option<Box<dyn Controller>>,
+    /// When the module started playing (higher is more recent).
+    activated: u64,
 }
 
 #[derive(Debug)]
@@ -23,23 +61,53 @@ pub struct Manager {
 
     modules: HashMap<usize, Module>,
     current_module: Option<usize>,
+    /// The module that was current most recently (used for controls).
+    last_module: Option<usize>,
 
     next_id: usize,
+    next_activation: u64,
+
+    /// Classifies the transitions between the published states.
+    classifier: Classifier,
+    seq: u64,
+
+    transliteration: Option<TransliterationMode>,
 }
 
 impl Manager {
     pub fn new(event_tx: watch::Sender<Event>) -> Self {
+        let (classifier, seq) = {
+            let initial = event_tx.borrow();
+            (Classifier::new(&initial.state), initial.seq)
+        };
         Self {
             event_tx,
+            classifier,
+            seq,
             modules: HashMap::default(),
             current_module: None,
+            last_module: None,
             next_id: 0,
+            next_activation: 0,
+            transliteration: None,
         }
     }
 
+    /// Romanizes the title and artist of all updates with the specified mode.
+    pub fn with_transliteration(mut self, mode: Option<TransliterationMode>) -> Self {
+        self.transliteration = mode;
+        self
+    }
+
     fn send_update_state(&mut self, updated: usize) {
         if let Ok(Some(state)) = self.update_state(updated) {
-            if let Err(e) = self.event_tx.send(state) {
+            self.seq += 1;
+            let update = Update {
+                seq: self.seq,
+                transitions: self.classifier.classify(&state),
+                state,
+            };
+            if let Err(e) = self.event_tx.send(Arc::new(update)) {
                 error!(error = %e,"Couldn't send state on event_tx");
             }
         }
@@ -49,7 +117,7 @@ impl Manager {
     /// Returns
     /// * `Ok(Some(..))` if a new state has to be sent
     /// * `Ok(None)`     is nothing changed
-    fn update_state(&mut self, updated: usize) -> anyhow::Result<Option<Event>> {
+    fn update_state(&mut self, updated: usize) -> anyhow::Result<Option<Arc<ModuleState>>> {
         let mut active: Vec<(usize, &Module)> = self
             .modules
             .iter()
@@ -68,8 +136,12 @@ impl Manager {
                 Some(Arc::new(ModuleState::Paused))
             }
         } else {
-            // sort descending
-            active.sort_by(|(_, a), (_, b)| b.priority.cmp(&a.priority));
+            // sort descending, the module that started playing last wins ties
+            active.sort_by(|(_, a), (_, b)| {
+                b.priority
+                    .cmp(&a.priority)
+                    .then(b.activated.cmp(&a.activated))
+            });
             let (id, module) = active.first().unwrap(); // we checked if the vec was empty
 
             // if the current module didn't change and the updated module was not the current one
@@ -83,6 +155,7 @@ impl Manager {
             }
 
             self.current_module = Some(*id);
+            self.last_module = Some(*id);
 
             event!(Level::DEBUG, id = updated, message = ?(module.state), "Send");
             Some(module.state.clone())
@@ -105,6 +178,8 @@ impl Handler<CreateModule> for Manager {
             Module {
                 priority: msg.priority,
                 state: Arc::new(ModuleState::Paused),
+                controller: None,
+                activated: 0,
             },
         );
         id
@@ -114,7 +189,13 @@ impl Handler<CreateModule> for Manager {
 impl Handler<UpdateModule> for Manager {
     type Result = ();
 
-    fn handle(&mut self, msg: UpdateModule, _: &mut Self::Context) -> Self::Result {
+    fn handle(&mut self, mut msg: UpdateModule, _: &mut Self::Context) -> Self::Result {
+        if let (Some(mode), ModuleState::Playing(ref mut info)) =
+            (self.transliteration, &mut msg.state)
+        {
+            transliteration::apply(info, mode);
+        }
+
         let current_priority = self
             .current_module
             .as_ref()
@@ -126,6 +207,12 @@ impl Handler<UpdateModule> for Manager {
                 state = ?msg.state,
                 current_priority = ?current_priority,
                 module.priority = module.priority,  "Update");
+            if matches!(*module.state, ModuleState::Paused)
+                && matches!(msg.state, ModuleState::Playing(_))
+            {
+                self.next_activation += 1;
+                module.activated = self.next_activation;
+            }
             module.state = Arc::new(msg.state);
 
             if current_priority.map_or(true, |current_priority| module.priority >= current_priority)
@@ -140,8 +227,76 @@ impl Handler<RemoveModule> for Manager {
     type Result = ();
 
     fn handle(&mut self, msg: RemoveModule, _: &mut Self::Context) -> Self::Result {
+        if self.last_module == Some(msg.id) {
+            self.last_module = None;
+        }
         if self.modules.remove(&msg.id).is_some() && self.current_module == Some(msg.id) {
             self.send_update_state(msg.id);
         }
     }
 }
+
+impl Handler<SetPriority> for Manager {
+    type Result = ();
+
+    fn handle(&mut self, msg: SetPriority, _: &mut Self::Context) -> Self::Result {
+        let Some(module) = self.modules.get_mut(&msg.id) else {
+            return;
+        };
+        if module.priority != msg.priority {
+            module.priority = msg.priority;
+            self.send_update_state(msg.id);
+        }
+    }
+}
+
+impl Handler<SetController> for Manager {
+    type Result = ();
+
+    fn handle(&mut self, msg: SetController, _: &mut Self::Context) -> Self::Result {
+        if let Some(module) = self.modules.get_mut(&msg.id) {
+            module.controller = Some(msg.controller);
+        }
+    }
+}
+
+impl Handler<Control> for Manager {
+    type Result = ResponseFuture<Result<(), ControlError>>;
+
+    fn handle(&mut self, msg: Control, _: &mut Self::Context) -> Self::Result {
+        let controller = msg
+            .id
+            .or(self.current_module)
+            .or(self.last_module)
+            .ok_or(ControlError::NoModule)
+            .and_then(|id| self.modules.get(&id).ok_or(ControlError::UnknownModule(id)))
+            .and_then(|module| module.controller.as_ref().ok_or(ControlError::Unsupported));
+        match controller {
+            Ok(controller) => {
+                event!(Level::DEBUG, action = ?msg.action, "Control");
+                controller.control(msg.action)
+            }
+            Err(e) => Box::pin(async move { Err(e) }),
+        }
+    }
+}
+
+impl Handler<ListModules> for Manager {
+    type Result = Vec<ModuleInfo>;
+
+    fn handle(&mut self, _: ListModules, _: &mut Self::Context) -> Self::Result {
+        let mut modules: Vec<_> = self
+            .modules
+            .iter()
+            .map(|(id, module)| ModuleInfo {
+                id: *id,
+                priority: module.priority,
+                current: self.current_module == Some(*id),
+                controllable: module.controller.is_some(),
+                state: (*module.state).clone(),
+            })
+            .collect();
+        modules.sort_by_key(|module| module.id);
+        modules
+    }
+}
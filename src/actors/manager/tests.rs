@@ -6,39 +6,42 @@ use crate::model::PlayInfo;
 
 #[actix::test]
 async fn basic_play() -> anyhow::Result<()> {
-    let (event_tx, event_rx) = watch::channel(Arc::new(ModuleState::Paused));
+    let (event_tx, event_rx) = watch::channel(Update::initial());
     let manager = Manager::new(event_tx).start();
     let module_id = manager.send(CreateModule { priority: 1 }).await?;
 
     manager.send(UpdateModule::paused(module_id)).await?;
-    assert_eq!(**event_rx.borrow(), ModuleState::Paused);
+    assert_eq!(*event_rx.borrow().state, ModuleState::Paused);
 
     let song1 = PlayInfo::simple("Song1", "Artist1", "test");
     manager
         .send(UpdateModule::playing(module_id, song1.clone()))
         .await?;
-    assert_eq!(**event_rx.borrow(), ModuleState::Playing(song1.clone()));
+    assert_eq!(
+        *event_rx.borrow().state,
+        ModuleState::Playing(song1.clone())
+    );
 
     manager.send(RemoveModule { id: module_id }).await?;
-    assert_eq!(**event_rx.borrow(), ModuleState::Paused);
+    assert_eq!(*event_rx.borrow().state, ModuleState::Paused);
     manager
         .send(UpdateModule::playing(module_id, song1))
         .await?;
-    assert_eq!(**event_rx.borrow(), ModuleState::Paused);
+    assert_eq!(*event_rx.borrow().state, ModuleState::Paused);
 
     Ok(())
 }
 
 #[actix::test]
 async fn priority_play() -> anyhow::Result<()> {
-    let (event_tx, event_rx) = watch::channel(Arc::new(ModuleState::Paused));
+    let (event_tx, event_rx) = watch::channel(Update::initial());
     let manager = Manager::new(event_tx).start();
     let high_prio = manager.send(CreateModule { priority: 5 }).await?;
     let low_prio = manager.send(CreateModule { priority: 1 }).await?;
 
     manager.send(UpdateModule::paused(high_prio)).await?;
     manager.send(UpdateModule::paused(low_prio)).await?;
-    assert_eq!(**event_rx.borrow(), ModuleState::Paused);
+    assert_eq!(*event_rx.borrow().state, ModuleState::Paused);
 
     let song1 = PlayInfo::simple("Song1", "Artist1", "test");
     let song2 = PlayInfo::simple("Song2", "Artist2", "test");
@@ -46,28 +49,208 @@ async fn priority_play() -> anyhow::Result<()> {
     manager
         .send(UpdateModule::playing(low_prio, song1.clone()))
         .await?;
-    assert_eq!(**event_rx.borrow(), ModuleState::Playing(song1.clone()));
+    assert_eq!(
+        *event_rx.borrow().state,
+        ModuleState::Playing(song1.clone())
+    );
     manager
         .send(UpdateModule::playing(high_prio, song2.clone()))
         .await?;
-    assert_eq!(**event_rx.borrow(), ModuleState::Playing(song2.clone()));
+    assert_eq!(
+        *event_rx.borrow().state,
+        ModuleState::Playing(song2.clone())
+    );
     manager
         .send(UpdateModule::playing(low_prio, song3.clone()))
         .await?;
-    assert_eq!(**event_rx.borrow(), ModuleState::Playing(song2.clone()));
+    assert_eq!(
+        *event_rx.borrow().state,
+        ModuleState::Playing(song2.clone())
+    );
     manager.send(UpdateModule::paused(high_prio)).await?;
-    assert_eq!(**event_rx.borrow(), ModuleState::Playing(song3.clone()));
+    assert_eq!(
+        *event_rx.borrow().state,
+        ModuleState::Playing(song3.clone())
+    );
 
     manager.send(RemoveModule { id: high_prio }).await?;
-    assert_eq!(**event_rx.borrow(), ModuleState::Playing(song3.clone()));
+    assert_eq!(
+        *event_rx.borrow().state,
+        ModuleState::Playing(song3.clone())
+    );
     manager
         .send(UpdateModule::playing(high_prio, song1))
         .await?;
-    assert_eq!(**event_rx.borrow(), ModuleState::Playing(song3));
+    assert_eq!(*event_rx.borrow().state, ModuleState::Playing(song3));
     manager.send(RemoveModule { id: low_prio }).await?;
-    assert_eq!(**event_rx.borrow(), ModuleState::Paused);
+    assert_eq!(*event_rx.borrow().state, ModuleState::Paused);
     manager.send(UpdateModule::playing(low_prio, song2)).await?;
-    assert_eq!(**event_rx.borrow(), ModuleState::Paused);
+    assert_eq!(*event_rx.borrow().state, ModuleState::Paused);
 
     Ok(())
 }
+
+#[actix::test]
+async fn equal_priority_play() -> anyhow::Result<()> {
+    let (event_tx, event_rx) = watch::channel(Update::initial());
+    let manager = Manager::new(event_tx).start();
+    let first = manager.send(CreateModule { priority: 1 }).await?;
+    let second = manager.send(CreateModule { priority: 1 }).await?;
+
+    let song1 = PlayInfo::simple("Song1", "Artist1", "test");
+    let song2 = PlayInfo::simple("Song2", "Artist2", "test");
+    let song3 = PlayInfo::simple("Song3", "Artist3", "test");
+    manager
+        .send(UpdateModule::playing(first, song1.clone()))
+        .await?;
+    manager
+        .send(UpdateModule::playing(second, song2.clone()))
+        .await?;
+    // the module that started playing last wins
+    assert_eq!(
+        *event_rx.borrow().state,
+        ModuleState::Playing(song2.clone())
+    );
+    manager
+        .send(UpdateModule::playing(first, song3.clone()))
+        .await?;
+    assert_eq!(
+        *event_rx.borrow().state,
+        ModuleState::Playing(song2.clone())
+    );
+
+    manager.send(UpdateModule::paused(second)).await?;
+    assert_eq!(
+        *event_rx.borrow().state,
+        ModuleState::Playing(song3.clone())
+    );
+    manager
+        .send(UpdateModule::playing(second, song2.clone()))
+        .await?;
+    assert_eq!(*event_rx.borrow().state, ModuleState::Playing(song2));
+
+    manager
+        .send(SetPriority {
+            id: first,
+            priority: 2,
+        })
+        .await?;
+    assert_eq!(*event_rx.borrow().state, ModuleState::Playing(song3));
+
+    Ok(())
+}
+
+#[actix::test]
+async fn transliteration() -> anyhow::Result<()> {
+    let (event_tx, event_rx) = watch::channel(Update::initial());
+    let manager = Manager::new(event_tx)
+        .with_transliteration(Some(TransliterationMode::Add))
+        .start();
+    let module_id = manager.send(CreateModule { priority: 1 }).await?;
+
+    manager
+        .send(UpdateModule::playing(
+            module_id,
+            PlayInfo::simple("Кукушка", "Кино", "test"),
+        ))
+        .await?;
+    let ModuleState::Playing(ref info) = *event_rx.borrow().state else {
+        panic!("Expected a playing state");
+    };
+    assert_eq!(info.title, "Кукушка");
+    assert_eq!(info.title_latin.as_deref(), Some("Kukushka"));
+    assert_eq!(info.artist_latin.as_deref(), Some("Kino"));
+
+    Ok(())
+}
+
+#[actix::test]
+async fn publishes_transitions() -> anyhow::Result<()> {
+    let (event_tx, event_rx) = watch::channel(Update::initial());
+    let manager = Manager::new(event_tx).start();
+    let module_id = manager.send(CreateModule { priority: 1 }).await?;
+
+    let song1 = PlayInfo::simple("Song1", "Artist1", "test");
+    manager
+        .send(UpdateModule::playing(module_id, song1.clone()))
+        .await?;
+    assert_eq!(
+        **event_rx.borrow(),
+        Update {
+            seq: 1,
+            state: Arc::new(ModuleState::Playing(song1.clone())),
+            transitions: vec![EventKind::Resumed(None), EventKind::TrackChanged(song1)],
+        }
+    );
+
+    manager.send(UpdateModule::paused(module_id)).await?;
+    assert_eq!(event_rx.borrow().seq, 2);
+    assert_eq!(event_rx.borrow().transitions, vec![EventKind::Paused]);
+
+    Ok(())
+}
+
+#[test]
+fn classify_transitions() {
+    use crate::model::{ImageInfo, TimelineInfo};
+
+    let timeline = |ts, progress_ms, rate| TimelineInfo {
+        ts,
+        duration_ms: 60_000,
+        progress_ms,
+        rate,
+    };
+    let mut song1 = PlayInfo::simple("Song1", "Artist1", "test");
+    song1.timeline = Some(timeline(10_000, 0, 1.0));
+    let playing = |info: &PlayInfo| ModuleState::Playing(info.clone());
+
+    let mut classifier = Classifier::new(&ModuleState::Paused);
+    assert_eq!(classifier.classify(&ModuleState::Paused), vec![]);
+    assert_eq!(
+        classifier.classify(&playing(&song1)),
+        vec![
+            EventKind::Resumed(song1.timeline.clone()),
+            EventKind::TrackChanged(song1.clone())
+        ]
+    );
+    assert_eq!(classifier.classify(&playing(&song1)), vec![]);
+
+    // regular progress update
+    song1.timeline = Some(timeline(15_000, 5_200, 1.0));
+    assert_eq!(classifier.classify(&playing(&song1)), vec![]);
+
+    song1.timeline = Some(timeline(16_000, 30_000, 1.0));
+    assert_eq!(
+        classifier.classify(&playing(&song1)),
+        vec![EventKind::Seeked(song1.timeline.clone())]
+    );
+
+    song1.timeline = Some(timeline(17_000, 31_000, 2.0));
+    assert_eq!(
+        classifier.classify(&playing(&song1)),
+        vec![EventKind::RateChanged(song1.timeline.clone().unwrap())]
+    );
+
+    song1.image = Some(ImageInfo::External("https://example.com/a.png".to_owned()));
+    assert_eq!(
+        classifier.classify(&playing(&song1)),
+        vec![EventKind::ArtChanged(song1.image.clone())]
+    );
+
+    assert_eq!(
+        classifier.classify(&ModuleState::Paused),
+        vec![EventKind::Paused]
+    );
+    // the position changes while paused - this isn't a seek
+    song1.timeline = Some(timeline(60_000, 40_000, 2.0));
+    assert_eq!(
+        classifier.classify(&playing(&song1)),
+        vec![EventKind::Resumed(song1.timeline.clone())]
+    );
+
+    let song2 = PlayInfo::simple("Song2", "Artist1", "test");
+    assert_eq!(
+        classifier.classify(&playing(&song2)),
+        vec![EventKind::TrackChanged(song2.clone())]
+    );
+}
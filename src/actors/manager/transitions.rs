@@ -0,0 +1,94 @@
+use crate::model::{ImageInfo, ModuleState, PlayInfo, TimelineInfo};
+use serde::Serialize;
+
+/// Difference between the extrapolated and the reported position,
+/// after which a timeline update is considered a seek.
+const SEEK_TOLERANCE_MS: u64 = 1500;
+
+/// A classified transition between two states.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", content = "data")]
+pub enum EventKind {
+    TrackChanged(PlayInfo),
+    Seeked(Option<TimelineInfo>),
+    ArtChanged(Option<ImageInfo>),
+    RateChanged(TimelineInfo),
+    Paused,
+    Resumed(Option<TimelineInfo>),
+}
+
+/// Classifies transitions between consecutive states.
+///
+/// The manager classifies the states it publishes, so every consumer sees the same transitions.
+#[derive(Debug, Default)]
+pub struct Classifier {
+    last: Option<PlayInfo>,
+    paused: bool,
+}
+
+impl Classifier {
+    /// Creates a classifier starting at `state`.
+    pub fn new(state: &ModuleState) -> Self {
+        let mut this = Self::default();
+        this.classify(state);
+        this
+    }
+
+    /// Returns the transitions from the previous state to `state`.
+    pub fn classify(&mut self, state: &ModuleState) -> Vec<EventKind> {
+        let mut kinds = Vec::new();
+        let info = match state {
+            ModuleState::Paused => {
+                if !self.paused {
+                    self.paused = true;
+                    kinds.push(EventKind::Paused);
+                }
+                return kinds;
+            }
+            ModuleState::Playing(info) => info,
+        };
+
+        let last = self.last.replace(info.clone());
+        if std::mem::take(&mut self.paused) {
+            kinds.push(EventKind::Resumed(info.timeline.clone()));
+        }
+        let Some(last) = last.filter(|last| same_track(last, info)) else {
+            kinds.push(EventKind::TrackChanged(info.clone()));
+            return kinds;
+        };
+
+        if last.image != info.image {
+            kinds.push(EventKind::ArtChanged(info.image.clone()));
+        }
+        match (&last.timeline, &info.timeline) {
+            (Some(old), Some(new)) => {
+                #[allow(clippy::float_cmp)] // rates are reported, not computed
+                if old.rate != new.rate {
+                    kinds.push(EventKind::RateChanged(new.clone()));
+                }
+                if is_seek(old, new) && !kinds.iter().any(|k| matches!(k, EventKind::Resumed(_))) {
+                    kinds.push(EventKind::Seeked(Some(new.clone())));
+                }
+            }
+            (None, None) => (),
+            (_, new) => kinds.push(EventKind::Seeked(new.clone())),
+        }
+        kinds
+    }
+}
+
+/// Compares everything except the image and timeline.
+fn same_track(a: &PlayInfo, b: &PlayInfo) -> bool {
+    a.title == b.title
+        && a.artist == b.artist
+        && a.track_number == b.track_number
+        && a.album == b.album
+        && a.source == b.source
+        && a.title_latin == b.title_latin
+        && a.artist_latin == b.artist_latin
+}
+
+fn is_seek(old: &TimelineInfo, new: &TimelineInfo) -> bool {
+    let expected = old.position_at(new.ts);
+    expected.abs_diff(new.progress_ms) > SEEK_TOLERANCE_MS
+}
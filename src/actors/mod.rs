@@ -1,3 +1,4 @@
 pub mod client_ws;
 pub mod extension_ws;
 pub mod manager;
+pub mod push;
@@ -0,0 +1,183 @@
+#[cfg(test)]
+mod tests;
+
+use crate::{
+    actors::manager::{self, Manager},
+    config::PushConfig,
+    image_store::{ImageStore, SlotRef},
+    model::{ImageInfo, InternalImage, ModuleState, PlayInfo},
+};
+use actix::{
+    Actor, ActorFutureExt, Addr, AsyncContext, AtomicResponse, Context, Handler, MailboxError,
+    Message, SpawnHandle, WrapFuture,
+};
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+use tracing::{debug, event, Level};
+
+/// Manages the modules fed by `POST /api/push/{name}`.
+pub struct PushActor {
+    manager: Addr<Manager>,
+    image_store: Arc<RwLock<ImageStore>>,
+    config: PushConfig,
+
+    modules: HashMap<String, PushModule>,
+}
+
+struct PushModule {
+    id: usize,
+    image_id: SlotRef,
+    /// The uploaded cover and the track (title, artist) it belongs to.
+    cover: Option<(ImageInfo, (String, String))>,
+    expiry: Option<SpawnHandle>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type", content = "data")]
+#[allow(clippy::large_enum_variant)] // only used for deserialization
+pub enum PushState {
+    Active(PlayInfo),
+    Inactive,
+}
+
+pub struct Cover {
+    pub content_type: String,
+    pub data: Vec<u8>,
+}
+
+#[derive(Message)]
+#[rtype(result = "Result<(), MailboxError>")]
+pub struct Push {
+    pub name: String,
+    pub state: PushState,
+    pub cover: Option<Cover>,
+}
+
+impl PushActor {
+    pub fn new(
+        manager: Addr<Manager>,
+        image_store: Arc<RwLock<ImageStore>>,
+        config: PushConfig,
+    ) -> Self {
+        Self {
+            manager,
+            image_store,
+            config,
+            modules: HashMap::new(),
+        }
+    }
+
+    /// Applies an update to the module `name` and returns the update for the manager.
+    fn update(
+        &mut self,
+        name: &str,
+        state: PushState,
+        cover: Option<Cover>,
+        ctx: &mut Context<Self>,
+    ) -> Option<manager::UpdateModule> {
+        let (_, ttl) = self.config.module(name);
+        let module = self.modules.get_mut(name)?;
+        if let Some(handle) = module.expiry.take() {
+            ctx.cancel_future(handle);
+        }
+
+        let state = match state {
+            PushState::Active(mut info) => {
+                info.source = format!("push::{name}");
+                let track = (info.title.clone(), info.artist.clone());
+                if let Some(cover) = cover {
+                    let epoch_id = self.image_store.write().unwrap().store(
+                        *module.image_id,
+                        cover.content_type,
+                        cover.data,
+                    );
+                    let image = ImageInfo::Internal(InternalImage {
+                        id: *module.image_id,
+                        epoch_id,
+                    });
+                    module.cover = Some((image, track.clone()));
+                }
+                // keep the uploaded cover for updates of the same track
+                if info.image.is_none() {
+                    info.image = module
+                        .cover
+                        .as_ref()
+                        .filter(|(_, cover_track)| *cover_track == track)
+                        .map(|(image, _)| image.clone());
+                }
+
+                ModuleState::Playing(info)
+            }
+            PushState::Inactive => ModuleState::Paused,
+        };
+        // removing the module drops its cover, so unused names don't accumulate
+        let name = name.to_owned();
+        module.expiry = Some(ctx.run_later(ttl, move |this, _| {
+            if let Some(module) = this.modules.remove(&name) {
+                debug!(name, "Push module expired");
+                this.manager
+                    .do_send(manager::RemoveModule { id: module.id });
+            }
+        }));
+        Some(manager::UpdateModule {
+            id: module.id,
+            state,
+        })
+    }
+}
+
+impl Actor for PushActor {
+    type Context = Context<Self>;
+}
+
+impl Handler<Push> for PushActor {
+    type Result = AtomicResponse<Self, Result<(), MailboxError>>;
+
+    fn handle(&mut self, msg: Push, _: &mut Self::Context) -> Self::Result {
+        let create = if self.modules.contains_key(&msg.name) {
+            None
+        } else {
+            let (priority, _) = self.config.module(&msg.name);
+            Some(self.manager.send(manager::CreateModule { priority }))
+        };
+
+        AtomicResponse::new(Box::pin(
+            async move {
+                match create {
+                    Some(create) => create.await.map(Some),
+                    None => Ok(None),
+                }
+            }
+            .into_actor(self)
+            .then(move |created, this, ctx| {
+                let update = created.map(|created| {
+                    if let Some(id) = created {
+                        event!(Level::DEBUG, name = msg.name, id, "Creating push module");
+                        this.modules.insert(
+                            msg.name.clone(),
+                            PushModule {
+                                id,
+                                image_id: SlotRef::new(&this.image_store),
+                                cover: None,
+                                expiry: None,
+                            },
+                        );
+                    }
+                    this.update(&msg.name, msg.state, msg.cover, ctx)
+                });
+                let manager = this.manager.clone();
+                async move {
+                    match update {
+                        Ok(Some(update)) => manager.send(update).await,
+                        Ok(None) => Ok(()),
+                        Err(e) => Err(e),
+                    }
+                }
+                .into_actor(this)
+            }),
+        ))
+    }
+}
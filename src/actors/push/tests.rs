@@ -0,0 +1,176 @@
+use super::*;
+use crate::config::PushModuleConfig;
+use std::time::Duration;
+use tokio::sync::watch;
+
+fn start(config: PushConfig) -> (watch::Receiver<manager::Event>, Addr<PushActor>) {
+    let (event_tx, event_rx) = watch::channel(manager::Update::initial());
+    let manager = Manager::new(event_tx).start();
+    let image_store = Arc::new(RwLock::new(ImageStore::new()));
+    (
+        event_rx,
+        PushActor::new(manager, image_store, config).start(),
+    )
+}
+
+fn playing(rx: &watch::Receiver<manager::Event>) -> PlayInfo {
+    match *rx.borrow().state {
+        ModuleState::Playing(ref info) => info.clone(),
+        ModuleState::Paused => panic!("Expected a playing state"),
+    }
+}
+
+#[actix::test]
+async fn push_and_expire() -> anyhow::Result<()> {
+    let config = PushConfig {
+        modules: HashMap::from([(
+            "dj".to_owned(),
+            PushModuleConfig {
+                priority: None,
+                ttl_secs: Some(1),
+            },
+        )]),
+        ..PushConfig::default()
+    };
+    let (rx, push) = start(config);
+
+    push.send(Push {
+        name: "dj".to_owned(),
+        state: PushState::Active(PlayInfo::simple("Title", "Artist", "ignored")),
+        cover: None,
+    })
+    .await??;
+    let info = playing(&rx);
+    assert_eq!(info.title, "Title");
+    assert_eq!(info.source, "push::dj");
+
+    tokio::time::sleep(Duration::from_millis(1200)).await;
+    assert_eq!(*rx.borrow().state, ModuleState::Paused);
+
+    push.send(Push {
+        name: "dj".to_owned(),
+        state: PushState::Active(PlayInfo::simple("Title", "Artist", "")),
+        cover: None,
+    })
+    .await??;
+    assert_eq!(playing(&rx).title, "Title");
+    push.send(Push {
+        name: "dj".to_owned(),
+        state: PushState::Inactive,
+        cover: None,
+    })
+    .await??;
+    assert_eq!(*rx.borrow().state, ModuleState::Paused);
+
+    Ok(())
+}
+
+#[actix::test]
+async fn priorities() -> anyhow::Result<()> {
+    let config = PushConfig {
+        modules: HashMap::from([(
+            "game".to_owned(),
+            PushModuleConfig {
+                priority: Some(5),
+                ttl_secs: None,
+            },
+        )]),
+        ..PushConfig::default()
+    };
+    let (rx, push) = start(config);
+
+    push.send(Push {
+        name: "game".to_owned(),
+        state: PushState::Active(PlayInfo::simple("Game", "Soundtrack", "")),
+        cover: None,
+    })
+    .await??;
+    push.send(Push {
+        name: "dj".to_owned(),
+        state: PushState::Active(PlayInfo::simple("Title", "Artist", "")),
+        cover: None,
+    })
+    .await??;
+    assert_eq!(playing(&rx).source, "push::game");
+
+    Ok(())
+}
+
+#[actix::test]
+async fn cover_upload() -> anyhow::Result<()> {
+    let (rx, push) = start(PushConfig::default());
+
+    push.send(Push {
+        name: "dj".to_owned(),
+        state: PushState::Active(PlayInfo::simple("Title", "Artist", "")),
+        cover: Some(Cover {
+            content_type: "image/png".to_owned(),
+            data: vec![1, 2, 3],
+        }),
+    })
+    .await??;
+    let image = playing(&rx).image;
+    assert!(matches!(image, Some(ImageInfo::Internal(_))));
+
+    // the cover is kept for the same track
+    push.send(Push {
+        name: "dj".to_owned(),
+        state: PushState::Active(PlayInfo::simple("Title", "Artist", "")),
+        cover: None,
+    })
+    .await??;
+    assert_eq!(playing(&rx).image, image);
+
+    push.send(Push {
+        name: "dj".to_owned(),
+        state: PushState::Active(PlayInfo::simple("Other", "Artist", "")),
+        cover: None,
+    })
+    .await??;
+    assert_eq!(playing(&rx).image, None);
+
+    Ok(())
+}
+
+#[actix::test]
+async fn removes_expired_modules() -> anyhow::Result<()> {
+    let (event_tx, rx) = watch::channel(manager::Update::initial());
+    let manager = Manager::new(event_tx).start();
+    let image_store = Arc::new(RwLock::new(ImageStore::new()));
+    let config = PushConfig {
+        ttl_secs: 1,
+        ..PushConfig::default()
+    };
+    let push = PushActor::new(manager.clone(), image_store.clone(), config).start();
+
+    push.send(Push {
+        name: "a".to_owned(),
+        state: PushState::Active(PlayInfo::simple("Title", "Artist", "")),
+        cover: Some(Cover {
+            content_type: "image/png".to_owned(),
+            data: vec![1, 2, 3],
+        }),
+    })
+    .await??;
+    push.send(Push {
+        name: "b".to_owned(),
+        state: PushState::Inactive,
+        cover: None,
+    })
+    .await??;
+    let Some(ImageInfo::Internal(image)) = playing(&rx).image else {
+        panic!("Expected an uploaded cover");
+    };
+    assert_eq!(manager.send(manager::ListModules).await?.len(), 2);
+
+    // inactive modules expire as well
+    tokio::time::sleep(Duration::from_millis(1200)).await;
+    assert!(manager.send(manager::ListModules).await?.is_empty());
+    assert!(image_store
+        .read()
+        .unwrap()
+        .get(image.id, image.epoch_id)
+        .is_none());
+
+    Ok(())
+}
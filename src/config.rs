@@ -6,9 +6,12 @@ use crate::{
 };
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::HashMap,
     fs,
+    net::SocketAddr,
     path::{Path, PathBuf},
     sync::OnceLock,
+    time::Duration,
 };
 use tracing::warn;
 
@@ -20,6 +23,44 @@ pub struct Config {
     pub no_autostart: bool,
     pub modules: ModuleConfig,
     pub server: ServerConfig,
+    pub transliteration: TransliterationConfig,
+}
+
+impl Config {
+    /// How long images replaced by a newer one can still be requested.
+    ///
+    /// Delayed outputs refer to images that might already be replaced.
+    /// Clients get some more time to load the image after the event was sent.
+    pub fn image_retention(&self) -> Duration {
+        let delay_ms = self
+            .server
+            .client_delay_ms
+            .max(self.server.rest_delay_ms)
+            .max(self.modules.cover.delay_ms);
+        if delay_ms == 0 {
+            Duration::ZERO
+        } else {
+            Duration::from_millis(delay_ms) + Duration::from_secs(5)
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct TransliterationConfig {
+    #[serde(default = "bool_false")]
+    pub enabled: bool,
+    pub mode: TransliterationMode,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum TransliterationMode {
+    /// Add `title_latin` and `artist_latin`.
+    #[default]
+    Add,
+    /// Replace the title and artist.
+    Replace,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -30,18 +71,172 @@ pub struct ServerConfig {
     pub custom_theme_path: String,
     #[serde(default = "default_custom_script_path")]
     pub custom_script_path: String,
+    /// Delay of the events sent to clients in milliseconds.
+    #[serde(default)]
+    pub client_delay_ms: u64,
+    /// Delay of the state returned by the REST API in milliseconds.
+    #[serde(default)]
+    pub rest_delay_ms: u64,
+    #[serde(default)]
+    pub auth: AuthConfig,
+    /// Certificate of listeners with `tls = true` (unless they have their own).
+    #[serde(default)]
+    pub certificate: CertificateConfig,
+    /// Listeners with their own routes. If this is empty, `port`/`bind` serve all routes.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub listeners: Vec<ListenerConfig>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ListenerConfig {
+    #[serde(flatten)]
+    pub bind: BindConfig,
+    /// The route groups served by this listener.
+    #[serde(default = "RouteGroup::all")]
+    pub routes: Vec<RouteGroup>,
+}
+
+/// Routes sharing a token and a listener.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RouteGroup {
+    /// The overlay, WebSocket clients, SSE, REST and images.
+    Display,
+    /// The browser extension and push.
+    Ingest,
+    /// Controlling players.
+    Control,
+}
+
+impl RouteGroup {
+    pub fn all() -> Vec<Self> {
+        vec![Self::Display, Self::Ingest, Self::Control]
+    }
+}
+
+/// Tokens required to access the API. Routes of groups without a token are open.
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct AuthConfig {
+    /// Reading the state (WebSocket clients, SSE, REST and images).
+    pub display: Option<String>,
+    /// Updating the state (browser extension and push).
+    pub ingest: Option<String>,
+    /// Controlling players.
+    pub control: Option<String>,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(untagged)]
 pub enum BindConfig {
-    Single { port: u16 },
-    Multiple { bind: Vec<std::net::SocketAddr> },
+    Single {
+        port: u16,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        tls: bool,
+    },
+    Multiple {
+        bind: Vec<BindAddress>,
+    },
 }
 
 impl Default for BindConfig {
     fn default() -> Self {
-        Self::Single { port: 48457 }
+        Self::Single {
+            port: 48457,
+            tls: false,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum BindAddress {
+    Plain(SocketAddr),
+    Detailed {
+        address: SocketAddr,
+        #[serde(default)]
+        tls: bool,
+        /// Certificate of this address (defaults to `server.certificate`).
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        certificate: Option<CertificateConfig>,
+    },
+    /// A Unix domain socket (Unix only).
+    Unix {
+        path: PathBuf,
+        /// Permissions of the socket file.
+        #[serde(default = "default_socket_mode")]
+        mode: u32,
+    },
+}
+
+fn default_socket_mode() -> u32 {
+    0o600
+}
+
+impl ServerConfig {
+    /// The configured listeners or a single one serving all routes on `port`/`bind`.
+    pub fn listeners(&self) -> Vec<ListenerConfig> {
+        if self.listeners.is_empty() {
+            vec![ListenerConfig {
+                bind: self.bind.clone(),
+                routes: RouteGroup::all(),
+            }]
+        } else {
+            self.listeners.clone()
+        }
+    }
+
+    /// Checks if any listener uses TLS with `certificate`.
+    pub fn uses_default_certificate(&self) -> bool {
+        self.listeners()
+            .iter()
+            .any(|listener| match &listener.bind {
+                BindConfig::Single { tls, .. } => *tls,
+                BindConfig::Multiple { bind } => bind.iter().any(|address| {
+                    matches!(
+                        address,
+                        BindAddress::Detailed {
+                            tls: true,
+                            certificate: None,
+                            ..
+                        }
+                    )
+                }),
+            })
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct CertificateConfig {
+    /// PEM encoded certificate chain (relative to the config).
+    pub cert: PathBuf,
+    /// PEM encoded private key (relative to the config).
+    pub key: PathBuf,
+    /// Generate a self-signed certificate if `cert` and `key` don't exist.
+    pub generate_self_signed: bool,
+    /// Names and IP addresses of the generated certificate.
+    pub subject_alt_names: Vec<String>,
+}
+
+impl Default for CertificateConfig {
+    fn default() -> Self {
+        Self {
+            cert: "cert.pem".into(),
+            key: "key.pem".into(),
+            generate_self_signed: true,
+            subject_alt_names: vec!["localhost".to_owned(), "127.0.0.1".to_owned()],
+        }
+    }
+}
+
+impl CertificateConfig {
+    /// Resolves `path` relative to the directory of the config.
+    pub fn resolve(path: &Path) -> PathBuf {
+        match current_config_path().parent() {
+            Some(dir) if path.is_relative() => dir.join(path),
+            _ => path.to_owned(),
+        }
     }
 }
 
@@ -51,6 +246,11 @@ impl Default for ServerConfig {
             bind: BindConfig::default(),
             custom_theme_path: default_custom_theme_path(),
             custom_script_path: default_custom_script_path(),
+            client_delay_ms: 0,
+            rest_delay_ms: 0,
+            auth: AuthConfig::default(),
+            certificate: CertificateConfig::default(),
+            listeners: Vec::new(),
         }
     }
 }
@@ -70,6 +270,10 @@ fn default_custom_script_path() -> String {
 pub struct ModuleConfig {
     #[serde(default)]
     pub file: FileOutputConfig,
+    #[serde(default)]
+    pub cover: CoverOutputConfig,
+    #[serde(default)]
+    pub push: PushConfig,
     #[cfg(windows)]
     #[cfg_attr(windows, serde(default))]
     pub gsmtc: GsmtcConfig,
@@ -87,6 +291,78 @@ pub struct FileOutputConfig {
     pub path: PathBuf,
     #[serde(default = "default_format")]
     pub format: String,
+    #[serde(default)]
+    pub marquee: Option<MarqueeConfig>,
+    #[serde(default)]
+    pub mode: FileOutputMode,
+    /// Columns of the `csv-log` mode (interpolation names).
+    #[serde(default = "default_csv_columns")]
+    pub columns: Vec<String>,
+    /// Size in bytes after which log files are rotated.
+    #[serde(default = "default_log_max_size")]
+    pub max_size: u64,
+    /// Number of rotated log files to keep.
+    #[serde(default = "default_log_max_backups")]
+    pub max_backups: usize,
+    /// Encoding of the `text` and `csv-log` modes.
+    #[serde(default)]
+    pub encoding: TextEncoding,
+    /// Sanitization of the `text` mode.
+    #[serde(default)]
+    pub sanitize: SanitizeConfig,
+    /// Delay of the output in milliseconds.
+    #[serde(default)]
+    pub delay_ms: u64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum TextEncoding {
+    #[default]
+    Utf8,
+    Utf8Bom,
+    Utf16leBom,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct SanitizeConfig {
+    pub strip_emoji: bool,
+    /// Strip control characters (except line feeds) and bidi controls.
+    pub strip_control: bool,
+    pub normalization: Option<Normalization>,
+    /// Maximum length of the encoded text in bytes (excluding the BOM).
+    pub max_bytes: Option<usize>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Normalization {
+    Nfc,
+    Nfkc,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum FileOutputMode {
+    /// Write the formatted text.
+    #[default]
+    Text,
+    /// Write the full state as JSON.
+    Json,
+    /// Append one JSON line per track.
+    NdjsonLog,
+    /// Append one CSV line per track.
+    CsvLog,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct MarqueeConfig {
+    pub width: usize,
+    #[serde(default = "default_marquee_separator")]
+    pub separator: String,
+    #[serde(default = "default_marquee_interval")]
+    pub interval_ms: u64,
 }
 
 fn default_file_path() -> PathBuf {
@@ -97,16 +373,131 @@ fn default_format() -> String {
     "{artist} - {title}".into()
 }
 
+fn default_csv_columns() -> Vec<String> {
+    ["artist", "title", "album-name?", "source"]
+        .map(String::from)
+        .to_vec()
+}
+
+fn default_log_max_size() -> u64 {
+    10 * 1024 * 1024
+}
+
+fn default_log_max_backups() -> usize {
+    3
+}
+
+fn default_marquee_separator() -> String {
+    " | ".into()
+}
+
+fn default_marquee_interval() -> u64 {
+    300
+}
+
 impl Default for FileOutputConfig {
     fn default() -> Self {
         Self {
             enabled: false,
             path: default_file_path(),
             format: default_format(),
+            marquee: None,
+            mode: FileOutputMode::default(),
+            columns: default_csv_columns(),
+            max_size: default_log_max_size(),
+            max_backups: default_log_max_backups(),
+            encoding: TextEncoding::default(),
+            sanitize: SanitizeConfig::default(),
+            delay_ms: 0,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct CoverOutputConfig {
+    #[serde(default = "bool_false")]
+    pub enabled: bool,
+    #[serde(default = "default_cover_path")]
+    pub path: PathBuf,
+    /// Convert all images to this format.
+    /// If this is `None`, images are written as they were received.
+    #[serde(default)]
+    pub format: Option<CoverFormat>,
+    /// Image to write if nothing is playing or the song doesn't have any cover art.
+    /// If this is `None`, a transparent 1x1 PNG is written.
+    #[serde(default)]
+    pub placeholder: Option<PathBuf>,
+    /// Delay of the output in milliseconds.
+    #[serde(default)]
+    pub delay_ms: u64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum CoverFormat {
+    Png,
+    Jpeg,
+    Webp,
+}
+
+fn default_cover_path() -> PathBuf {
+    "cover.png".into()
+}
+
+impl Default for CoverOutputConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: default_cover_path(),
+            format: None,
+            placeholder: None,
+            delay_ms: 0,
         }
     }
 }
 
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct PushConfig {
+    #[serde(default = "bool_false")]
+    pub enabled: bool,
+    /// Priority of modules which aren't configured in `modules`.
+    pub priority: u8,
+    /// Seconds after the last update until a module is removed.
+    pub ttl_secs: u64,
+    /// Overrides for specific names.
+    pub modules: HashMap<String, PushModuleConfig>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct PushModuleConfig {
+    pub priority: Option<u8>,
+    pub ttl_secs: Option<u64>,
+}
+
+impl Default for PushConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            priority: 1,
+            ttl_secs: 60,
+            modules: HashMap::new(),
+        }
+    }
+}
+
+impl PushConfig {
+    /// Returns the priority and TTL of the module `name`.
+    pub fn module(&self, name: &str) -> (u8, Duration) {
+        let module = self.modules.get(name);
+        (
+            module.and_then(|m| m.priority).unwrap_or(self.priority),
+            Duration::from_secs(module.and_then(|m| m.ttl_secs).unwrap_or(self.ttl_secs)),
+        )
+    }
+}
+
 cfg_unix! {
     #[derive(Deserialize, Serialize, Debug, Clone)]
     #[serde(default)]
@@ -170,7 +561,6 @@ lazy_static::lazy_static! {
     };
 }
 
-#[cfg(windows)]
 pub fn current_config_path() -> &'static Path {
     CURRENT_CONFIG_PATH.get_or_init(|| default_config_paths()[0].clone())
 }
@@ -1,8 +1,9 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, VecDeque},
     fmt::{Debug, Formatter},
     ops::Deref,
     sync::{Arc, RwLock},
+    time::{Duration, Instant},
 };
 
 pub struct Image {
@@ -22,6 +23,18 @@ impl Debug for Image {
 pub struct ImageStore {
     images: HashMap<usize, (usize, Option<Image>)>,
     next_id: usize,
+    /// Images that were replaced, cleared or removed.
+    /// They can still be requested until `retention` passed, because delayed events refer to them.
+    superseded: VecDeque<Superseded>,
+    retention: Duration,
+}
+
+#[derive(Debug)]
+struct Superseded {
+    at: Instant,
+    slot: usize,
+    epoch: usize,
+    image: Image,
 }
 
 impl ImageStore {
@@ -29,9 +42,17 @@ impl ImageStore {
         Self {
             images: HashMap::default(),
             next_id: 0,
+            superseded: VecDeque::new(),
+            retention: Duration::ZERO,
         }
     }
 
+    /// Keeps superseded images for `retention`.
+    pub fn with_retention(mut self, retention: Duration) -> Self {
+        self.retention = retention;
+        self
+    }
+
     pub fn create_id(&mut self) -> usize {
         let id = self.next_id;
         self.next_id = self.next_id.overflowing_add(1).0;
@@ -44,12 +65,20 @@ impl ImageStore {
             .get(&id)
             .filter(|i| i.0 == target_epoch)
             .and_then(|(_, i)| i.as_ref())
+            .or_else(|| {
+                self.superseded
+                    .iter()
+                    .filter(|old| old.at.elapsed() < self.retention)
+                    .find(|old| old.slot == id && old.epoch == target_epoch)
+                    .map(|old| &old.image)
+            })
     }
 
     pub fn store(&mut self, slot: usize, content_type: String, data: Vec<u8>) -> usize {
         if let Some(img) = self.images.get_mut(&slot) {
             let epoch = img.0.overflowing_add(1).0;
-            *img = (epoch, Some(Image { content_type, data }));
+            let old = std::mem::replace(img, (epoch, Some(Image { content_type, data })));
+            self.supersede(slot, old);
             epoch
         } else {
             self.images
@@ -60,12 +89,33 @@ impl ImageStore {
 
     pub fn clear(&mut self, slot: usize) {
         if let Some(img) = self.images.get_mut(&slot) {
-            img.1 = None;
+            let old = (img.0, img.1.take());
+            self.supersede(slot, old);
         }
     }
 
     pub fn remove(&mut self, slot: usize) {
-        self.images.remove(&slot);
+        if let Some(old) = self.images.remove(&slot) {
+            self.supersede(slot, old);
+        }
+    }
+
+    fn supersede(&mut self, slot: usize, (epoch, image): (usize, Option<Image>)) {
+        while self
+            .superseded
+            .front()
+            .is_some_and(|old| old.at.elapsed() >= self.retention)
+        {
+            self.superseded.pop_front();
+        }
+        if let Some(image) = image.filter(|_| !self.retention.is_zero()) {
+            self.superseded.push_back(Superseded {
+                at: Instant::now(),
+                slot,
+                epoch,
+                image,
+            });
+        }
     }
 }
 
@@ -13,6 +13,7 @@ mod logging;
 mod model;
 mod repositories;
 mod static_files;
+mod transliteration;
 #[cfg(windows)]
 mod win_setup;
 mod workers;
@@ -24,35 +25,62 @@ use config::{ModuleConfig, CONFIG};
 use std::sync::Arc;
 
 use crate::{
-    actors::manager::{self, Manager},
+    actors::{
+        client_ws::ServerInfo,
+        extension_ws::ResumeStore,
+        manager::{self, Manager},
+        push::PushActor,
+    },
     image_store::ImageStore,
     logging::init_logging,
     model::ModuleState,
     repositories::init_repositories,
-    workers::file_output::output_to_file,
+    repositories::RestEvents,
+    workers::{cover_output::output_cover_to_file, delay::delayed, file_output::output_to_file},
 };
 use actix::{Actor, Addr};
 use actix_web::{web, App, HttpServer};
-use std::sync::RwLock;
+use std::{sync::RwLock, time::Duration};
 use tokio::sync::watch;
 use tracing_actix_web::TracingLogger;
 
 fn init_channels() -> (watch::Receiver<manager::Event>, Addr<Manager>) {
-    let (event_tx, event_rx) = watch::channel(Arc::new(ModuleState::Paused));
+    let (event_tx, event_rx) = watch::channel(manager::Update::initial());
 
-    let manager = Manager::new(event_tx).start();
+    let manager = Manager::new(event_tx)
+        .with_transliteration(
+            CONFIG
+                .transliteration
+                .enabled
+                .then_some(CONFIG.transliteration.mode),
+        )
+        .start();
 
     (event_rx, manager)
 }
 
 fn init_common_actors(
     modules: &'static ModuleConfig,
-    event_rx: &watch::Receiver<Arc<ModuleState>>,
+    event_rx: &watch::Receiver<manager::Event>,
+    image_store: &Arc<RwLock<ImageStore>>,
 ) {
     if modules.file.enabled {
-        let event_rx = event_rx.clone();
+        let event_rx = delayed(
+            event_rx.clone(),
+            Duration::from_millis(modules.file.delay_ms),
+        );
         tokio::spawn(async move {
-            output_to_file(&modules.file.path, &modules.file.format, event_rx).await;
+            output_to_file(&modules.file, event_rx).await;
+        });
+    }
+    if modules.cover.enabled {
+        let event_rx = delayed(
+            event_rx.clone(),
+            Duration::from_millis(modules.cover.delay_ms),
+        );
+        let image_store = image_store.clone();
+        tokio::spawn(async move {
+            output_cover_to_file(&modules.cover, image_store, event_rx).await;
         });
     }
 }
@@ -71,15 +99,13 @@ async fn init_windows_actors(
 }
 
 #[cfg(unix)]
-async fn init_unix_actors(
+fn init_unix_actors(
     modules: &'static ModuleConfig,
     manager: Addr<Manager>,
     image_store: Arc<RwLock<ImageStore>>,
 ) {
     if modules.dbus.enabled {
-        workers::dbus::start_spawning(manager, image_store)
-            .await
-            .unwrap();
+        workers::dbus::start_spawning(&modules.dbus, manager, image_store);
     }
 }
 
@@ -87,44 +113,145 @@ async fn init_unix_actors(
 async fn async_main() -> std::io::Result<()> {
     let (event_rx, manager) = init_channels();
 
-    let image_store = Arc::new(RwLock::new(ImageStore::new()));
+    let image_store = Arc::new(RwLock::new(
+        ImageStore::new().with_retention(CONFIG.image_retention()),
+    ));
 
-    init_common_actors(&CONFIG.modules, &event_rx);
+    init_common_actors(&CONFIG.modules, &event_rx, &image_store);
 
     #[cfg(windows)]
     init_windows_actors(&CONFIG.modules, manager.clone(), image_store.clone()).await;
 
     #[cfg(unix)]
-    init_unix_actors(&CONFIG.modules, manager.clone(), image_store.clone()).await;
+    init_unix_actors(&CONFIG.modules, manager.clone(), image_store.clone());
+
+    let push = CONFIG.modules.push.enabled.then(|| {
+        web::Data::new(
+            PushActor::new(
+                manager.clone(),
+                image_store.clone(),
+                CONFIG.modules.push.clone(),
+            )
+            .start(),
+        )
+    });
 
+    let resume_store = web::Data::new(ResumeStore::new(manager.clone()).start());
     let image_store: web::Data<_> = image_store.into();
     let manager = web::Data::new(manager);
-    let event_rx = web::Data::new(event_rx);
-    let srv = HttpServer::new(move || {
-        App::new()
+    let server_info = web::Data::new(ServerInfo::from_config(&CONFIG));
+    let auth = web::Data::new(CONFIG.server.auth.clone());
+    let rest_events = web::Data::new(RestEvents(delayed(
+        event_rx.clone(),
+        Duration::from_millis(CONFIG.server.rest_delay_ms),
+    )));
+    let event_rx = web::Data::new(delayed(
+        event_rx,
+        Duration::from_millis(CONFIG.server.client_delay_ms),
+    ));
+    let app_data = move |config: &mut web::ServiceConfig| {
+        if let Some(ref push) = push {
+            config.app_data(push.clone());
+        }
+        config
             .app_data(event_rx.clone())
+            .app_data(rest_events.clone())
+            .app_data(server_info.clone())
+            .app_data(auth.clone())
             .app_data(image_store.clone())
-            .app_data(manager.clone())
-            .wrap(TracingLogger::default())
-            .service(web::scope("api").configure(init_repositories))
-            .service(static_files::theme_css)
-            .service(static_files::user_js)
-            .service(static_files::service())
-    })
-    .workers(2);
-
-    match &CONFIG.server.bind {
-        config::BindConfig::Single { port } => {
-            tracing::info!("Binding on 127.0.0.1:{port}");
-            srv.bind((std::net::Ipv4Addr::LOCALHOST, *port))
-        }
-        config::BindConfig::Multiple { bind } => {
-            tracing::info!("Binding on {bind:?}");
-            srv.bind(&bind[..])
+            .app_data(resume_store.clone())
+            .app_data(manager.clone());
+    };
+
+    let tls = CONFIG
+        .server
+        .uses_default_certificate()
+        .then(|| utilities::tls::load_server_config(&CONFIG.server.certificate))
+        .transpose()?;
+    let listeners = CONFIG.server.listeners();
+    let mut servers = Vec::with_capacity(listeners.len());
+    for listener in &listeners {
+        let app_data = app_data.clone();
+        let routes = listener.routes.clone();
+        let srv = HttpServer::new(move || {
+            let mut app = App::new()
+                .configure(app_data.clone())
+                .wrap(TracingLogger::default())
+                .service(web::scope("api").configure(|config| init_repositories(config, &routes)));
+            if routes.contains(&config::RouteGroup::Display) {
+                app = app
+                    .service(static_files::theme_css)
+                    .service(static_files::user_js)
+                    .service(static_files::service());
+            }
+            app
+        })
+        .workers(2);
+
+        let srv = match &listener.bind {
+            config::BindConfig::Single { port, tls: use_tls } => {
+                let address = (std::net::Ipv4Addr::LOCALHOST, *port);
+                tracing::info!(tls = use_tls, routes = ?listener.routes, "Binding on 127.0.0.1:{port}");
+                match tls {
+                    Some(ref tls) if *use_tls => srv.bind_rustls_0_23(address, tls.clone())?,
+                    _ => srv.bind(address)?,
+                }
+            }
+            config::BindConfig::Multiple { bind } => {
+                tracing::info!(routes = ?listener.routes, "Binding on {bind:?}");
+                bind.iter()
+                    .try_fold(srv, |srv, address| match (address, &tls) {
+                        (
+                            config::BindAddress::Detailed {
+                                address,
+                                tls: true,
+                                certificate: Some(certificate),
+                            },
+                            _,
+                        ) => srv.bind_rustls_0_23(
+                            address,
+                            utilities::tls::load_server_config(certificate)?,
+                        ),
+                        (
+                            config::BindAddress::Detailed {
+                                address,
+                                tls: true,
+                                certificate: None,
+                            },
+                            Some(tls),
+                        ) => srv.bind_rustls_0_23(address, tls.clone()),
+                        (
+                            config::BindAddress::Plain(address)
+                            | config::BindAddress::Detailed { address, .. },
+                            _,
+                        ) => srv.bind(address),
+                        #[cfg(unix)]
+                        (config::BindAddress::Unix { path, mode }, _) => {
+                            srv.listen_uds(utilities::unix_socket::bind(path, *mode)?)
+                        }
+                        #[cfg(not(unix))]
+                        (config::BindAddress::Unix { .. }, _) => Err(std::io::Error::new(
+                            std::io::ErrorKind::Unsupported,
+                            "Unix sockets are only supported on Unix",
+                        )),
+                    })?
+            }
+        };
+        servers.push(srv.run());
+    }
+    let result = futures::future::try_join_all(servers).await.map(|_| ());
+
+    #[cfg(unix)]
+    for listener in &listeners {
+        if let config::BindConfig::Multiple { bind } = &listener.bind {
+            for address in bind {
+                if let config::BindAddress::Unix { path, .. } = address {
+                    std::fs::remove_file(path).ok();
+                }
+            }
         }
-    }?
-    .run()
-    .await
+    }
+    result
 }
 
 fn main() -> std::io::Result<()> {
@@ -2,6 +2,7 @@ use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Debug, Clone, PartialEq)]
 #[serde(tag = "type", content = "data")]
+#[allow(clippy::large_enum_variant)] // most states are `Playing`
 pub enum ModuleState {
     Playing(PlayInfo),
     Paused,
@@ -18,7 +19,15 @@ pub struct PlayInfo {
     pub timeline: Option<TimelineInfo>,
     pub album: Option<AlbumInfo>,
 
+    #[serde(default)]
     pub source: String,
+
+    /// Romanized title (only set if transliteration is enabled).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title_latin: Option<String>,
+    /// Romanized artist (only set if transliteration is enabled).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub artist_latin: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
@@ -53,6 +62,26 @@ pub struct AlbumInfo {
     pub track_count: u32,
 }
 
+impl TimelineInfo {
+    /// Extrapolates the playback position at `now_ms` (UTC timestamp in milliseconds).
+    ///
+    /// The position is clamped to the duration of the song.
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    pub fn position_at(&self, now_ms: u64) -> u64 {
+        let elapsed = now_ms.saturating_sub(self.ts) as f64 * f64::from(self.rate.max(0.0));
+        let position = self.progress_ms.saturating_add(elapsed as u64);
+        if self.duration_ms > 0 {
+            position.min(self.duration_ms)
+        } else {
+            position
+        }
+    }
+}
+
 impl PlayInfo {
     #[cfg(test)]
     pub fn simple<Title, Artist, Source>(title: Title, artist: Artist, source: Source) -> Self
@@ -69,6 +98,39 @@ impl PlayInfo {
             timeline: None,
             album: None,
             source: source.into(),
+            title_latin: None,
+            artist_latin: None,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extrapolates_position() {
+        let timeline = TimelineInfo {
+            ts: 10_000,
+            duration_ms: 60_000,
+            progress_ms: 5_000,
+            rate: 1.0,
+        };
+        assert_eq!(timeline.position_at(10_000), 5_000);
+        assert_eq!(timeline.position_at(12_500), 7_500);
+        // captured in the future (e.g. delayed)
+        assert_eq!(timeline.position_at(9_000), 5_000);
+        assert_eq!(timeline.position_at(1_000_000), 60_000);
+
+        let fast = TimelineInfo {
+            rate: 2.0,
+            ..timeline.clone()
+        };
+        assert_eq!(fast.position_at(12_500), 10_000);
+        let stopped = TimelineInfo {
+            rate: 0.0,
+            ..timeline
+        };
+        assert_eq!(stopped.position_at(12_500), 5_000);
+    }
+}
@@ -0,0 +1,222 @@
+use crate::config::{AuthConfig, RouteGroup};
+use actix_web::{
+    body::EitherBody,
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    error,
+    http::{header, Method},
+    web, Error,
+};
+use futures::future::{ready, LocalBoxFuture, Ready};
+use serde::Deserialize;
+use tracing::debug;
+
+#[derive(Deserialize)]
+struct TokenQuery {
+    token: String,
+}
+
+/// Rejects requests to the routes of `group` without a valid token.
+///
+/// The token is taken from the `Authorization: Bearer {token}` header or the `token` query parameter.
+/// Every group's scope is wrapped separately, so the group comes from the matched route rather
+/// than the raw (percent-encoded) path.
+/// Requests are rejected before they reach any handler, so no actor is started for them.
+pub fn check_token(group: RouteGroup) -> CheckToken {
+    CheckToken(group)
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CheckToken(RouteGroup);
+
+impl<S, B> Transform<S, ServiceRequest> for CheckToken
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = CheckTokenMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(CheckTokenMiddleware {
+            service,
+            group: self.0,
+        }))
+    }
+}
+
+pub struct CheckTokenMiddleware<S> {
+    service: S,
+    group: RouteGroup,
+}
+
+impl<S, B> Service<ServiceRequest> for CheckTokenMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        if !is_authorized(self.group, &req) {
+            debug!(path = req.path(), group = ?self.group, "Rejected unauthorized request");
+            let res = req.error_response(error::ErrorUnauthorized("Invalid or missing token"));
+            return Box::pin(ready(Ok(res.map_into_right_body())));
+        }
+        let fut = self.service.call(req);
+        Box::pin(async move { fut.await.map(ServiceResponse::map_into_left_body) })
+    }
+}
+
+fn is_authorized(group: RouteGroup, req: &ServiceRequest) -> bool {
+    // CORS preflight requests never contain credentials
+    if req.method() == Method::OPTIONS {
+        return true;
+    }
+    let Some(config) = req.app_data::<web::Data<AuthConfig>>() else {
+        return true;
+    };
+
+    let expected = match group {
+        RouteGroup::Display => config.display.as_deref(),
+        RouteGroup::Ingest => config.ingest.as_deref(),
+        RouteGroup::Control => config.control.as_deref(),
+    };
+    expected.is_none_or(|expected| {
+        request_token(req)
+            .is_some_and(|token| constant_time_eq(token.as_bytes(), expected.as_bytes()))
+    })
+}
+
+fn request_token(req: &ServiceRequest) -> Option<String> {
+    let bearer = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    if let Some(token) = bearer {
+        return Some(token.trim().to_owned());
+    }
+    web::Query::<TokenQuery>::from_query(req.query_string())
+        .ok()
+        .map(|query| query.into_inner().token)
+}
+
+/// Compares two byte strings in constant time (only the length is leaked).
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{http::StatusCode, test as actix_test, App, HttpResponse};
+
+    #[test]
+    fn comparison() {
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secret2"));
+        assert!(!constant_time_eq(b"", b"secret"));
+    }
+
+    fn ok() -> actix_web::Route {
+        web::to(|| async { HttpResponse::Ok().finish() })
+    }
+
+    #[actix_web::test]
+    async fn rejects_requests() {
+        let config = AuthConfig {
+            display: None,
+            ingest: Some("ingest-token".to_owned()),
+            control: Some("control-token".to_owned()),
+        };
+        let app = actix_test::init_service(
+            App::new().app_data(web::Data::new(config)).service(
+                web::scope("/api")
+                    .service(
+                        web::scope("/ws/client")
+                            .wrap(check_token(RouteGroup::Display))
+                            .default_service(ok()),
+                    )
+                    .service(
+                        web::scope("/ws/extension")
+                            .wrap(check_token(RouteGroup::Ingest))
+                            .default_service(ok()),
+                    )
+                    .service(
+                        web::scope("/push")
+                            .wrap(check_token(RouteGroup::Ingest))
+                            .default_service(ok()),
+                    )
+                    .service(
+                        web::scope("/control")
+                            .wrap(check_token(RouteGroup::Control))
+                            .default_service(ok()),
+                    ),
+            ),
+        )
+        .await;
+        let status = |req: actix_test::TestRequest| {
+            let app = &app;
+            async move {
+                actix_test::call_service(app, req.to_request())
+                    .await
+                    .status()
+            }
+        };
+
+        assert_eq!(
+            status(actix_test::TestRequest::get().uri("/api/ws/client")).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(actix_test::TestRequest::get().uri("/api/ws/extension")).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(actix_test::TestRequest::get().uri("/api/ws/extension?token=wrong")).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(actix_test::TestRequest::get().uri("/api/ws/extension?token=control-token"))
+                .await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(actix_test::TestRequest::get().uri("/api/ws/extension?token=ingest-token"))
+                .await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(
+                actix_test::TestRequest::post()
+                    .uri("/api/push/dj")
+                    .insert_header((header::AUTHORIZATION, "Bearer ingest-token"))
+            )
+            .await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(actix_test::TestRequest::post().uri("/api/control/play")).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(
+                actix_test::TestRequest::default()
+                    .method(Method::OPTIONS)
+                    .uri("/api/push/dj")
+            )
+            .await,
+            StatusCode::OK
+        );
+    }
+}
@@ -0,0 +1,203 @@
+#![allow(clippy::unused_async)] // required by the actix macros
+
+use crate::actors::manager::{Control, ControlAction, ControlError, ListModules, Manager};
+use actix::Addr;
+use actix_web::{error, get, post, web, HttpResponse, Result};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct ControlQuery {
+    /// The id of the module to control (defaults to the current one).
+    module: Option<usize>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SeekRequest {
+    #[serde(rename_all = "camelCase")]
+    Offset { offset_ms: i64 },
+    #[serde(rename_all = "camelCase")]
+    Position { position_ms: u64 },
+}
+
+fn into_response_error(e: ControlError) -> error::Error {
+    match e {
+        ControlError::NoModule | ControlError::UnknownModule(_) => error::ErrorNotFound(e),
+        ControlError::Unsupported => error::ErrorNotImplemented(e),
+        ControlError::Failed(_) => error::ErrorBadGateway(e),
+        ControlError::Timeout => error::ErrorGatewayTimeout(e),
+    }
+}
+
+async fn control(
+    manager: &Addr<Manager>,
+    query: ControlQuery,
+    action: ControlAction,
+) -> Result<HttpResponse> {
+    manager
+        .send(Control {
+            id: query.module,
+            action,
+        })
+        .await
+        .map_err(error::ErrorInternalServerError)?
+        .map_err(into_response_error)?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[post("/seek")]
+async fn seek(
+    manager: web::Data<Addr<Manager>>,
+    query: web::Query<ControlQuery>,
+    body: web::Json<SeekRequest>,
+) -> Result<HttpResponse> {
+    let action = match body.into_inner() {
+        SeekRequest::Offset { offset_ms } => ControlAction::Seek { offset_ms },
+        SeekRequest::Position { position_ms } => ControlAction::SeekTo { position_ms },
+    };
+    control(&manager, query.into_inner(), action).await
+}
+
+#[post("/{action}")]
+async fn simple_action(
+    manager: web::Data<Addr<Manager>>,
+    query: web::Query<ControlQuery>,
+    path: web::Path<String>,
+) -> Result<HttpResponse> {
+    let action = match path.as_str() {
+        "play" => ControlAction::Play,
+        "pause" => ControlAction::Pause,
+        "toggle" => ControlAction::Toggle,
+        "next" => ControlAction::Next,
+        "previous" => ControlAction::Previous,
+        _ => return Err(error::ErrorNotFound("Unknown action")),
+    };
+    control(&manager, query.into_inner(), action).await
+}
+
+#[get("/modules")]
+async fn list_modules(manager: web::Data<Addr<Manager>>) -> Result<HttpResponse> {
+    let modules = manager
+        .send(ListModules)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(modules))
+}
+
+pub fn init_control(config: &mut web::ServiceConfig) {
+    config
+        .service(list_modules)
+        .service(seek)
+        .service(simple_action);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        actors::manager::{Controller, CreateModule, SetController, Update, UpdateModule},
+        model::PlayInfo,
+    };
+    use actix::Actor;
+    use actix_web::{http::StatusCode, test, App};
+    use futures::future::BoxFuture;
+    use std::sync::{Arc, Mutex};
+    use tokio::sync::watch;
+
+    /// Records the actions and rejects seeking.
+    struct Recorder(Arc<Mutex<Vec<ControlAction>>>);
+
+    impl Controller for Recorder {
+        fn control(&self, action: ControlAction) -> BoxFuture<'static, Result<(), ControlError>> {
+            let result = if matches!(action, ControlAction::Seek { .. }) {
+                Err(ControlError::Unsupported)
+            } else {
+                self.0.lock().unwrap().push(action);
+                Ok(())
+            };
+            Box::pin(async move { result })
+        }
+    }
+
+    #[actix_web::test]
+    async fn routes_actions() {
+        let (event_tx, _rx) = watch::channel(Update::initial());
+        let manager = Manager::new(event_tx).start();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(manager.clone()))
+                .service(web::scope("/api/control").configure(init_control)),
+        )
+        .await;
+        let post = |uri: &'static str| {
+            let app = &app;
+            async move {
+                test::call_service(app, test::TestRequest::post().uri(uri).to_request())
+                    .await
+                    .status()
+            }
+        };
+
+        assert_eq!(post("/api/control/play").await, StatusCode::NOT_FOUND);
+
+        let actions = Arc::new(Mutex::new(Vec::new()));
+        let controlled = manager.send(CreateModule { priority: 0 }).await.unwrap();
+        let other = manager.send(CreateModule { priority: 0 }).await.unwrap();
+        manager
+            .send(SetController {
+                id: controlled,
+                controller: Box::new(Recorder(actions.clone())),
+            })
+            .await
+            .unwrap();
+        manager
+            .send(UpdateModule::playing(
+                controlled,
+                PlayInfo::simple("Title", "Artist", "test"),
+            ))
+            .await
+            .unwrap();
+
+        assert_eq!(post("/api/control/toggle").await, StatusCode::NO_CONTENT);
+        assert_eq!(post("/api/control/next").await, StatusCode::NO_CONTENT);
+        assert_eq!(
+            *actions.lock().unwrap(),
+            [ControlAction::Toggle, ControlAction::Next]
+        );
+
+        // the last active module is controlled after it paused
+        manager
+            .send(UpdateModule::paused(controlled))
+            .await
+            .unwrap();
+        assert_eq!(post("/api/control/play").await, StatusCode::NO_CONTENT);
+
+        let req = test::TestRequest::post()
+            .uri("/api/control/seek")
+            .set_json(serde_json::json!({ "offsetMs": 5000 }))
+            .to_request();
+        assert_eq!(
+            test::call_service(&app, req).await.status(),
+            StatusCode::NOT_IMPLEMENTED
+        );
+
+        let uri = format!("/api/control/pause?module={other}");
+        let req = test::TestRequest::post().uri(&uri).to_request();
+        assert_eq!(
+            test::call_service(&app, req).await.status(),
+            StatusCode::NOT_IMPLEMENTED
+        );
+        assert_eq!(
+            post("/api/control/pause?module=1000").await,
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(post("/api/control/rewind").await, StatusCode::NOT_FOUND);
+
+        let req = test::TestRequest::get()
+            .uri("/api/control/modules")
+            .to_request();
+        let modules: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(modules[0]["controllable"], true);
+        assert_eq!(modules[1]["controllable"], false);
+    }
+}
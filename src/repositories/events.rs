@@ -0,0 +1,193 @@
+#![allow(clippy::unused_async)] // required by the actix macros
+
+use crate::manager;
+use actix_web::{get, http::header, web, web::Bytes, HttpRequest, HttpResponse};
+use futures::Stream;
+use std::{
+    convert::Infallible,
+    hash::{DefaultHasher, Hash, Hasher},
+    time::Duration,
+};
+use tokio::{sync::watch, time::MissedTickBehavior};
+use tracing::{event, warn, Level};
+
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Streams the state as server-sent events.
+///
+/// The id of an event identifies the state.
+/// If a client reconnects with the id of the current state, it's not sent again.
+#[get("")]
+async fn events(
+    req: HttpRequest,
+    events: web::Data<watch::Receiver<manager::Event>>,
+) -> HttpResponse {
+    let last_id = req
+        .headers()
+        .get("Last-Event-ID")
+        .and_then(|id| id.to_str().ok())
+        .map(ToOwned::to_owned);
+    event!(Level::DEBUG, ?last_id, "SSE client connected");
+
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header((header::CACHE_CONTROL, "no-cache"))
+        .streaming(event_stream(
+            events.get_ref().clone(),
+            last_id.as_deref(),
+            KEEP_ALIVE_INTERVAL,
+        ))
+}
+
+fn event_stream(
+    mut rx: watch::Receiver<manager::Event>,
+    last_id: Option<&str>,
+    keep_alive: Duration,
+) -> impl Stream<Item = Result<Bytes, Infallible>> {
+    let current_id = state_id(&rx.borrow_and_update());
+    if last_id != Some(current_id.as_str()) {
+        rx.mark_changed();
+    }
+    let mut interval =
+        tokio::time::interval_at(tokio::time::Instant::now() + keep_alive, keep_alive);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    futures::stream::unfold((rx, interval), |(mut rx, mut interval)| async move {
+        let chunk = tokio::select! {
+            changed = rx.changed() => {
+                changed.ok()?;
+                let state = rx.borrow_and_update().clone();
+                match serde_json::to_string(&*state.state) {
+                    Ok(json) => Bytes::from(format!("id: {}\ndata: {json}\n\n", state_id(&state))),
+                    Err(e) => {
+                        warn!(error = %e, "Cannot serialize json");
+                        Bytes::from_static(b": invalid state\n\n")
+                    }
+                }
+            }
+            _ = interval.tick() => Bytes::from_static(b": keep-alive\n\n"),
+        };
+        Some((Ok(chunk), (rx, interval)))
+    })
+}
+
+/// Identifies a state across connections.
+fn state_id(state: &manager::Event) -> String {
+    let mut hasher = DefaultHasher::new();
+    serde_json::to_string(&*state.state)
+        .unwrap_or_default()
+        .hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+pub fn init_events(config: &mut web::ServiceConfig) {
+    config.service(events);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{ModuleState, PlayInfo};
+    use actix_web::{
+        body::{BoxBody, MessageBody},
+        test, App,
+    };
+    use std::pin::Pin;
+
+    async fn next_chunk(body: &mut Pin<Box<BoxBody>>) -> String {
+        let chunk = std::future::poll_fn(|cx| body.as_mut().poll_next(cx))
+            .await
+            .unwrap()
+            .unwrap();
+        String::from_utf8(chunk.to_vec()).unwrap()
+    }
+
+    fn data_of(chunk: &str) -> serde_json::Value {
+        let data = chunk
+            .lines()
+            .find_map(|line| line.strip_prefix("data: "))
+            .unwrap();
+        serde_json::from_str(data).unwrap()
+    }
+
+    #[actix_web::test]
+    async fn streams_state() {
+        let (tx, rx) = watch::channel(manager::Update::initial());
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(rx))
+                .service(web::scope("/api/events").configure(init_events)),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/api/events").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/event-stream"
+        );
+        let mut resp = Box::pin(resp.into_body());
+
+        let chunk = next_chunk(&mut resp).await;
+        assert!(chunk.starts_with("id: "));
+        assert_eq!(data_of(&chunk), serde_json::json!({ "type": "Paused" }));
+
+        tx.send(manager::Update::with_state(
+            1,
+            ModuleState::Playing(PlayInfo::simple("Title", "Artist", "test")),
+        ))
+        .unwrap();
+        let chunk = next_chunk(&mut resp).await;
+        assert_eq!(data_of(&chunk)["type"], "Playing");
+        assert_eq!(data_of(&chunk)["data"]["title"], "Title");
+    }
+
+    #[actix_web::test]
+    async fn resumes_with_last_event_id() {
+        let (tx, rx) = watch::channel(manager::Update::initial());
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(rx))
+                .service(web::scope("/api/events").configure(init_events)),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/api/events").to_request();
+        let mut resp = Box::pin(test::call_service(&app, req).await.into_body());
+        let chunk = next_chunk(&mut resp).await;
+        let id = chunk.lines().next().unwrap().strip_prefix("id: ").unwrap();
+
+        // the client already has the current state
+        let req = test::TestRequest::get()
+            .uri("/api/events")
+            .insert_header(("Last-Event-ID", id))
+            .to_request();
+        let mut resumed = Box::pin(test::call_service(&app, req).await.into_body());
+        tx.send(manager::Update::with_state(
+            1,
+            ModuleState::Playing(PlayInfo::simple("Title", "Artist", "test")),
+        ))
+        .unwrap();
+        assert_eq!(data_of(&next_chunk(&mut resumed).await)["type"], "Playing");
+
+        // the client missed an update
+        let req = test::TestRequest::get()
+            .uri("/api/events")
+            .insert_header(("Last-Event-ID", id))
+            .to_request();
+        let mut outdated = Box::pin(test::call_service(&app, req).await.into_body());
+        assert_eq!(data_of(&next_chunk(&mut outdated).await)["type"], "Playing");
+    }
+
+    #[actix_web::test]
+    async fn keep_alive() {
+        use futures::StreamExt;
+
+        let (_tx, rx) = watch::channel(manager::Update::initial());
+        let id = state_id(&rx.borrow());
+        let stream = event_stream(rx, Some(&id), Duration::from_millis(20));
+        let chunks: Vec<_> = stream.take(2).map(Result::unwrap).collect().await;
+        assert_eq!(chunks, [": keep-alive\n\n", ": keep-alive\n\n"]);
+    }
+}
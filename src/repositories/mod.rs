@@ -1,15 +1,144 @@
+mod auth;
+mod control;
+mod events;
 mod img;
+mod push;
+mod state;
 mod ws;
 
+pub use auth::check_token;
+pub use state::RestEvents;
+
+use crate::config::RouteGroup;
 use actix_cors::Cors;
 use actix_web::{middleware::Compat, web};
 
-pub fn init_repositories(config: &mut web::ServiceConfig) {
-    config
-        .service(web::scope("/img").configure(img::init_img))
-        .service(
+/// Registers the API routes of the enabled `routes`.
+///
+/// Every group is wrapped in [`check_token`] for its own token.
+pub fn init_repositories(config: &mut web::ServiceConfig, routes: &[RouteGroup]) {
+    let display = routes.contains(&RouteGroup::Display);
+    let ingest = routes.contains(&RouteGroup::Ingest);
+    if display {
+        config
+            .service(
+                web::scope("/img")
+                    .wrap(check_token(RouteGroup::Display))
+                    .configure(img::init_img),
+            )
+            .service(
+                web::scope("/events")
+                    .wrap(check_token(RouteGroup::Display))
+                    .wrap(Compat::new(Cors::permissive()))
+                    .configure(events::init_events),
+            )
+            .service(
+                web::scope("/state")
+                    .wrap(check_token(RouteGroup::Display))
+                    .wrap(Compat::new(Cors::permissive()))
+                    .configure(state::init_state),
+            );
+    }
+    if ingest {
+        config.service(
+            web::scope("/push")
+                .wrap(check_token(RouteGroup::Ingest))
+                .configure(push::init_push),
+        );
+    }
+    if routes.contains(&RouteGroup::Control) {
+        config.service(
+            web::scope("/control")
+                .wrap(check_token(RouteGroup::Control))
+                .configure(control::init_control),
+        );
+    }
+    if display || ingest {
+        config.service(
             web::scope("/ws")
                 .wrap(Compat::new(Cors::permissive()))
-                .configure(ws::init_ws),
+                .configure(|config| ws::init_ws(config, display, ingest)),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{actors::manager::Update, config::AuthConfig};
+    use actix_web::{http::StatusCode, test, App};
+    use tokio::sync::watch;
+
+    #[actix_web::test]
+    async fn filters_route_groups() {
+        let (_tx, rx) = watch::channel(Update::initial());
+        let app = test::init_service(
+            App::new().app_data(web::Data::new(RestEvents(rx))).service(
+                web::scope("/api")
+                    .configure(|config| init_repositories(config, &[RouteGroup::Display])),
+            ),
+        )
+        .await;
+        let status = |uri: &'static str| {
+            let app = &app;
+            async move {
+                test::call_service(app, test::TestRequest::get().uri(uri).to_request())
+                    .await
+                    .status()
+            }
+        };
+
+        assert_eq!(status("/api/state").await, StatusCode::OK);
+        // the route exists (the request fails without the app data)
+        assert_ne!(status("/api/ws/client").await, StatusCode::NOT_FOUND);
+        assert_eq!(status("/api/ws/extension").await, StatusCode::NOT_FOUND);
+        assert_eq!(status("/api/push/dj").await, StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn checks_tokens_of_encoded_paths() {
+        let (_tx, rx) = watch::channel(Update::initial());
+        let auth = AuthConfig {
+            display: None,
+            ingest: Some("ingest-token".to_owned()),
+            control: Some("control-token".to_owned()),
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(RestEvents(rx)))
+                .app_data(web::Data::new(auth))
+                .service(
+                    web::scope("/api")
+                        .configure(|config| init_repositories(config, &RouteGroup::all())),
+                ),
+        )
+        .await;
+        let status = |req: test::TestRequest| {
+            let app = &app;
+            async move { test::call_service(app, req.to_request()).await.status() }
+        };
+
+        // actix routes on the decoded path
+        for uri in [
+            "/api/ws/%65xtension",
+            "/api/%77s/extension",
+            "/api/%70ush/dj",
+            "/api/%63ontrol/play",
+        ] {
+            let req = if uri.contains("ws") {
+                test::TestRequest::get()
+            } else {
+                test::TestRequest::post()
+            };
+            assert_eq!(
+                status(req.uri(uri)).await,
+                StatusCode::UNAUTHORIZED,
+                "{uri}"
+            );
+        }
+        assert_eq!(
+            status(test::TestRequest::get().uri("/api/%73tate")).await,
+            StatusCode::OK
         );
+    }
 }
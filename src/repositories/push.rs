@@ -0,0 +1,192 @@
+use crate::actors::push::{Cover, Push, PushActor, PushState};
+use actix::Addr;
+use actix_multipart::Multipart;
+use actix_web::{error, guard::GuardContext, http::header, mime, post, web, HttpResponse, Result};
+use futures::TryStreamExt;
+
+const MAX_STATE_SIZE: usize = 64 * 1024;
+const MAX_COVER_SIZE: usize = 10 * 1024 * 1024;
+
+fn is_multipart(ctx: &GuardContext) -> bool {
+    ctx.header::<header::ContentType>()
+        .is_some_and(|ct| ct.type_() == mime::MULTIPART)
+}
+
+/// Names are used in the source (`push::{name}`), so they're restricted to a safe subset.
+fn validate_name(name: &str) -> Result<()> {
+    if !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    {
+        Ok(())
+    } else {
+        Err(error::ErrorBadRequest("Invalid name"))
+    }
+}
+
+async fn push(
+    push: Option<web::Data<Addr<PushActor>>>,
+    name: String,
+    state: PushState,
+    cover: Option<Cover>,
+) -> Result<HttpResponse> {
+    let Some(push) = push else {
+        return Err(error::ErrorNotFound("Push is disabled"));
+    };
+    validate_name(&name)?;
+    push.send(Push { name, state, cover })
+        .await
+        .and_then(|res| res)
+        .map_err(error::ErrorInternalServerError)?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Expects a `state` field with the JSON state and an optional `cover` field with an image.
+#[post("/{name}", guard = "is_multipart")]
+async fn push_multipart(
+    path: web::Path<String>,
+    mut payload: Multipart,
+    actor: Option<web::Data<Addr<PushActor>>>,
+) -> Result<HttpResponse> {
+    let mut state = None;
+    let mut cover = None;
+    while let Some(mut field) = payload.try_next().await? {
+        let name = field.name().unwrap_or_default().to_owned();
+        let content_type = field.content_type().map(ToString::to_string);
+        let limit = match name.as_str() {
+            "state" => MAX_STATE_SIZE,
+            "cover" => MAX_COVER_SIZE,
+            _ => continue,
+        };
+
+        let mut data = Vec::new();
+        while let Some(chunk) = field.try_next().await? {
+            if data.len() + chunk.len() > limit {
+                return Err(error::ErrorPayloadTooLarge(format!(
+                    "'{name}' is too large"
+                )));
+            }
+            data.extend_from_slice(&chunk);
+        }
+
+        if name == "state" {
+            state = Some(serde_json::from_slice(&data).map_err(error::ErrorBadRequest)?);
+        } else {
+            let content_type = content_type
+                .filter(|ct| ct.starts_with("image/"))
+                .ok_or_else(|| error::ErrorUnsupportedMediaType("The cover must be an image"))?;
+            cover = Some(Cover { content_type, data });
+        }
+    }
+    let state = state.ok_or_else(|| error::ErrorBadRequest("Missing 'state'"))?;
+
+    push(actor, path.into_inner(), state, cover).await
+}
+
+#[post("/{name}")]
+async fn push_json(
+    path: web::Path<String>,
+    state: web::Json<PushState>,
+    actor: Option<web::Data<Addr<PushActor>>>,
+) -> Result<HttpResponse> {
+    push(actor, path.into_inner(), state.into_inner(), None).await
+}
+
+pub fn init_push(config: &mut web::ServiceConfig) {
+    config
+        .app_data(web::JsonConfig::default().limit(MAX_STATE_SIZE))
+        .service(push_multipart)
+        .service(push_json);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        actors::manager::{Manager, Update},
+        config::PushConfig,
+        image_store::ImageStore,
+        model::{ImageInfo, ModuleState},
+    };
+    use actix::Actor;
+    use actix_web::{http::StatusCode, test, App};
+    use std::sync::{Arc, RwLock};
+    use tokio::sync::watch;
+
+    #[actix_web::test]
+    async fn push_requests() {
+        let (event_tx, rx) = watch::channel(Update::initial());
+        let manager = Manager::new(event_tx).start();
+        let image_store = Arc::new(RwLock::new(ImageStore::new()));
+        let actor = PushActor::new(manager, image_store, PushConfig::default()).start();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(actor))
+                .service(web::scope("/api/push").configure(init_push)),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/api/push/dj")
+            .set_json(serde_json::json!({
+                "type": "Active",
+                "data": { "title": "Title", "artist": "Artist", "trackNumber": null, "image": null, "timeline": null, "album": null }
+            }))
+            .to_request();
+        assert_eq!(
+            test::call_service(&app, req).await.status(),
+            StatusCode::NO_CONTENT
+        );
+        assert!(
+            matches!(*rx.borrow().state, ModuleState::Playing(ref info) if info.source == "push::dj")
+        );
+
+        let body = "--b\r\n\
+            Content-Disposition: form-data; name=\"state\"\r\n\r\n\
+            {\"type\":\"Active\",\"data\":{\"title\":\"Title\",\"artist\":\"Artist\",\"trackNumber\":null,\"image\":null,\"timeline\":null,\"album\":null}}\r\n\
+            --b\r\n\
+            Content-Disposition: form-data; name=\"cover\"; filename=\"cover.png\"\r\n\
+            Content-Type: image/png\r\n\r\n\
+            png\r\n\
+            --b--\r\n";
+        let req = test::TestRequest::post()
+            .uri("/api/push/dj")
+            .insert_header((header::CONTENT_TYPE, "multipart/form-data; boundary=b"))
+            .set_payload(body)
+            .to_request();
+        assert_eq!(
+            test::call_service(&app, req).await.status(),
+            StatusCode::NO_CONTENT
+        );
+        assert!(matches!(
+            *rx.borrow().state,
+            ModuleState::Playing(ref info) if matches!(info.image, Some(ImageInfo::Internal(_)))
+        ));
+
+        let req = test::TestRequest::post()
+            .uri("/api/push/a%20b")
+            .set_json(serde_json::json!({ "type": "Inactive" }))
+            .to_request();
+        assert_eq!(
+            test::call_service(&app, req).await.status(),
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[actix_web::test]
+    async fn disabled() {
+        let app =
+            test::init_service(App::new().service(web::scope("/api/push").configure(init_push)))
+                .await;
+        let req = test::TestRequest::post()
+            .uri("/api/push/dj")
+            .set_json(serde_json::json!({ "type": "Inactive" }))
+            .to_request();
+        assert_eq!(
+            test::call_service(&app, req).await.status(),
+            StatusCode::NOT_FOUND
+        );
+    }
+}
@@ -0,0 +1,17 @@
+#![allow(clippy::unused_async)] // required by the actix macros
+
+use crate::manager;
+use actix_web::{get, web, HttpResponse};
+use tokio::sync::watch;
+
+/// Events for the REST API (possibly delayed).
+pub struct RestEvents(pub watch::Receiver<manager::Event>);
+
+#[get("")]
+async fn get_state(events: web::Data<RestEvents>) -> HttpResponse {
+    HttpResponse::Ok().json(&*events.0.borrow().state)
+}
+
+pub fn init_state(config: &mut web::ServiceConfig) {
+    config.service(get_state);
+}
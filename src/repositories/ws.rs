@@ -1,35 +1,105 @@
 #![allow(clippy::unused_async)] // required by the actix macros
 
+use super::check_token;
 use crate::{
-    actors::{client_ws::ClientWsSession, extension_ws::ExtensionWsSession, manager::Manager},
+    actors::{
+        client_ws::{ClientWsSession, Protocol, ServerInfo},
+        extension_ws::{self, ExtensionWsSession, ResumeStore},
+        manager::Manager,
+    },
+    config::RouteGroup,
+    image_store::ImageStore,
     manager,
 };
 use actix::Addr;
 use actix_web::{get, web, HttpRequest, HttpResponse, Result};
 use actix_web_actors::ws;
+use serde::Deserialize;
+use std::{sync::RwLock, time::Duration};
 use tokio::sync::watch;
 use tracing::{event, Level};
 
-#[get("/client")]
+#[derive(Deserialize)]
+struct ClientQuery {
+    /// Interval of `Progress` messages in milliseconds.
+    ticks: Option<u64>,
+}
+
+#[get("")]
 async fn client(
     req: HttpRequest,
     stream: web::Payload,
+    query: web::Query<ClientQuery>,
+    events: web::Data<watch::Receiver<manager::Event>>,
+    info: web::Data<ServerInfo>,
+) -> Result<HttpResponse> {
+    start_client(Protocol::Snapshots, &req, stream, &query, &events, info)
+}
+
+#[get("/v2")]
+async fn client_v2(
+    req: HttpRequest,
+    stream: web::Payload,
+    query: web::Query<ClientQuery>,
     events: web::Data<watch::Receiver<manager::Event>>,
+    info: web::Data<ServerInfo>,
+) -> Result<HttpResponse> {
+    start_client(Protocol::Events, &req, stream, &query, &events, info)
+}
+
+fn start_client(
+    protocol: Protocol,
+    req: &HttpRequest,
+    stream: web::Payload,
+    query: &ClientQuery,
+    events: &watch::Receiver<manager::Event>,
+    info: web::Data<ServerInfo>,
 ) -> Result<HttpResponse> {
-    event!(Level::DEBUG, "Client connected");
-    ws::start(ClientWsSession::new(events.get_ref().clone()), &req, stream)
+    event!(Level::DEBUG, ?protocol, ticks = ?query.ticks, "Client connected");
+    ws::start(
+        ClientWsSession::new(events.clone(), info.into_inner(), protocol)
+            .with_ticks(query.ticks.filter(|ms| *ms > 0).map(Duration::from_millis)),
+        req,
+        stream,
+    )
 }
 
-#[get("/extension")]
+#[get("")]
 async fn extension(
     req: HttpRequest,
     stream: web::Payload,
     manager: web::Data<Addr<Manager>>,
+    resume_store: web::Data<Addr<ResumeStore>>,
+    image_store: web::Data<RwLock<ImageStore>>,
 ) -> Result<HttpResponse> {
     event!(Level::DEBUG, "Extension connected");
-    ws::start(ExtensionWsSession::new(manager.into_inner()), &req, stream)
+    ws::WsResponseBuilder::new(
+        ExtensionWsSession::new(
+            manager.into_inner(),
+            resume_store.into_inner(),
+            image_store.into_inner(),
+        ),
+        &req,
+        stream,
+    )
+    .frame_size(extension_ws::MAX_FRAME_SIZE)
+    .start()
 }
 
-pub fn init_ws(config: &mut web::ServiceConfig) {
-    config.service(client).service(extension);
+pub fn init_ws(config: &mut web::ServiceConfig, display: bool, ingest: bool) {
+    if display {
+        config.service(
+            web::scope("/client")
+                .wrap(check_token(RouteGroup::Display))
+                .service(client)
+                .service(client_v2),
+        );
+    }
+    if ingest {
+        config.service(
+            web::scope("/extension")
+                .wrap(check_token(RouteGroup::Ingest))
+                .service(extension),
+        );
+    }
 }
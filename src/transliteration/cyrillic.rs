@@ -0,0 +1,91 @@
+/// Romanizes a (lowercase) Cyrillic letter.
+///
+/// This covers Russian, Ukrainian, Belarusian, Bulgarian and Serbian letters.
+/// Returns `None` if the character isn't a supported Cyrillic letter.
+pub fn romanize(c: char) -> Option<&'static str> {
+    Some(match c {
+        'а' => "a",
+        'б' => "b",
+        'в' => "v",
+        'г' | 'ґ' => "g",
+        'д' => "d",
+        'ђ' => "dj",
+        'е' | 'э' => "e",
+        'ё' => "yo",
+        'є' => "ye",
+        'ж' => "zh",
+        'з' => "z",
+        'ѕ' | 'џ' => "dz",
+        'и' | 'і' => "i",
+        'ї' => "yi",
+        'й' | 'ы' => "y",
+        'ј' => "j",
+        'к' => "k",
+        'л' => "l",
+        'љ' => "lj",
+        'м' => "m",
+        'н' => "n",
+        'њ' => "nj",
+        'о' => "o",
+        'п' => "p",
+        'р' => "r",
+        'с' => "s",
+        'т' => "t",
+        'ћ' => "c",
+        'у' | 'ў' => "u",
+        'ф' => "f",
+        'х' => "kh",
+        'ц' => "ts",
+        'ч' => "ch",
+        'ш' => "sh",
+        'щ' => "shch",
+        'ъ' | 'ь' => "",
+        'ю' => "yu",
+        'я' => "ya",
+        _ => return None,
+    })
+}
+
+/// Romanizes a Cyrillic character, keeping the case of uppercase letters.
+pub fn push_romanized(c: char, out: &mut String) -> bool {
+    let lower = c.to_lowercase().next().unwrap_or(c);
+    let Some(latin) = romanize(lower) else {
+        return false;
+    };
+    if lower == c {
+        out.push_str(latin);
+    } else {
+        let mut chars = latin.chars();
+        if let Some(first) = chars.next() {
+            out.extend(first.to_uppercase());
+            out.push_str(chars.as_str());
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn romanize_str(s: &str) -> String {
+        let mut out = String::new();
+        for c in s.chars() {
+            if !push_romanized(c, &mut out) {
+                out.push(c);
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn words() {
+        assert_eq!(romanize_str("Кино"), "Kino");
+        assert_eq!(romanize_str("Группа крови"), "Gruppa krovi");
+        assert_eq!(romanize_str("Жизнь"), "Zhizn");
+        assert_eq!(romanize_str("Щедрик"), "Shchedrik");
+        assert_eq!(romanize_str("Їжак"), "Yizhak");
+        assert_eq!(romanize_str("Љубав"), "Ljubav");
+        assert_eq!(romanize_str("ДДТ"), "DDT");
+    }
+}
@@ -0,0 +1,109 @@
+//! Romanization of Hangul syllables according to the
+//! [Revised Romanization of Korean](https://en.wikipedia.org/wiki/Revised_Romanization_of_Korean).
+//!
+//! Only the liaison of final consonants (e.g. 한국어 → hangugeo) is handled,
+//! other sound changes aren't applied.
+
+const SYLLABLE_START: u32 = 0xAC00;
+const SYLLABLE_END: u32 = 0xD7A3;
+const MEDIAL_COUNT: u32 = 21;
+const FINAL_COUNT: u32 = 28;
+/// Index of the initial ㄹ.
+const RIEUL_INITIAL: u32 = 5;
+/// Index of the silent initial ㅇ.
+const SILENT_INITIAL: u32 = 11;
+
+const INITIALS: [&str; 19] = [
+    "g", "kk", "n", "d", "tt", "r", "m", "b", "pp", "s", "ss", "", "j", "jj", "ch", "k", "t", "p",
+    "h",
+];
+const MEDIALS: [&str; 21] = [
+    "a", "ae", "ya", "yae", "eo", "e", "yeo", "ye", "o", "wa", "wae", "oe", "yo", "u", "wo", "we",
+    "wi", "yu", "eu", "ui", "i",
+];
+/// Finals at the end of a syllable (followed by a consonant or the end of a word).
+const FINALS: [&str; 28] = [
+    "", "k", "k", "k", "n", "n", "n", "t", "l", "k", "m", "l", "l", "l", "p", "l", "m", "p", "p",
+    "t", "t", "ng", "t", "t", "k", "t", "p", "t",
+];
+/// Finals followed by a vowel (the syllable starts with a silent ㅇ).
+/// For clusters, the second consonant moves to the next syllable.
+const FINALS_BEFORE_VOWEL: [&str; 28] = [
+    "", "g", "kk", "ks", "n", "nj", "n", "d", "r", "lg", "lm", "lb", "ls", "lt", "lp", "r", "m",
+    "b", "ps", "s", "ss", "ng", "j", "ch", "k", "t", "p", "",
+];
+
+struct Syllable {
+    initial: u32,
+    medial: u32,
+    last: u32,
+}
+
+fn decompose(c: char) -> Option<Syllable> {
+    let c = u32::from(c);
+    if !(SYLLABLE_START..=SYLLABLE_END).contains(&c) {
+        return None;
+    }
+    let index = c - SYLLABLE_START;
+    Some(Syllable {
+        initial: index / (MEDIAL_COUNT * FINAL_COUNT),
+        medial: (index % (MEDIAL_COUNT * FINAL_COUNT)) / FINAL_COUNT,
+        last: index % FINAL_COUNT,
+    })
+}
+
+pub fn is_syllable(c: char) -> bool {
+    decompose(c).is_some()
+}
+
+/// Romanizes a Hangul syllable.
+/// `prev` and `next` are the characters before and after `c`.
+pub fn push_romanized(c: char, prev: Option<char>, next: Option<char>, out: &mut String) -> bool {
+    let Some(syllable) = decompose(c) else {
+        return false;
+    };
+
+    match prev.and_then(decompose) {
+        // ㄹ followed by ㄹ is romanized as 'll'
+        Some(prev) if syllable.initial == RIEUL_INITIAL && FINALS[prev.last as usize] == "l" => {
+            out.push('l');
+        }
+        _ => out.push_str(INITIALS[syllable.initial as usize]),
+    }
+    out.push_str(MEDIALS[syllable.medial as usize]);
+    match next.and_then(decompose) {
+        Some(next) if next.initial == SILENT_INITIAL => {
+            out.push_str(FINALS_BEFORE_VOWEL[syllable.last as usize]);
+        }
+        _ => out.push_str(FINALS[syllable.last as usize]),
+    }
+    true
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn romanize_str(s: &str) -> String {
+        let chars: Vec<char> = s.chars().collect();
+        let mut out = String::new();
+        for (i, c) in chars.iter().enumerate() {
+            let prev = i.checked_sub(1).map(|i| chars[i]);
+            if !push_romanized(*c, prev, chars.get(i + 1).copied(), &mut out) {
+                out.push(*c);
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn words() {
+        assert_eq!(romanize_str("한글"), "hangeul");
+        assert_eq!(romanize_str("서울"), "seoul");
+        assert_eq!(romanize_str("방탄소년단"), "bangtansonyeondan");
+        assert_eq!(romanize_str("한국어"), "hangugeo");
+        assert_eq!(romanize_str("별빛"), "byeolbit");
+        assert_eq!(romanize_str("설레"), "seolle");
+        assert_eq!(romanize_str("좋은 날"), "joeun nal");
+    }
+}
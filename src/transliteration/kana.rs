@@ -0,0 +1,175 @@
+//! Romanization of Hiragana and Katakana using (modified) Hepburn romanization.
+//!
+//! Kanji can't be romanized without a dictionary, so they're kept as is.
+
+const HIRAGANA_START: u32 = 0x3041;
+const KATAKANA_OFFSET: u32 = 0x60;
+
+/// Romanization of U+3041 to U+3096.
+const HIRAGANA: [&str; 86] = [
+    "a", "a", "i", "i", "u", "u", "e", "e", "o", "o", // ぁ..お
+    "ka", "ga", "ki", "gi", "ku", "gu", "ke", "ge", "ko", "go", // か..ご
+    "sa", "za", "shi", "ji", "su", "zu", "se", "ze", "so", "zo", // さ..ぞ
+    "ta", "da", "chi", "ji", "", "tsu", "zu", "te", "de", "to", "do", // た..ど
+    "na", "ni", "nu", "ne", "no", // な..の
+    "ha", "ba", "pa", "hi", "bi", "pi", "fu", "bu", "pu", "he", "be", "pe", "ho", "bo",
+    "po", // は..ぽ
+    "ma", "mi", "mu", "me", "mo", // ま..も
+    "ya", "ya", "yu", "yu", "yo", "yo", // ゃ..よ
+    "ra", "ri", "ru", "re", "ro", // ら..ろ
+    "wa", "wa", "i", "e", "o", "n", "vu", "ka", "ke", // ゎ..ゖ
+];
+
+const SMALL_TSU: char = 'っ';
+const PROLONGED_SOUND_MARK: char = 'ー';
+const MIDDLE_DOT: char = '・';
+
+/// Converts Katakana to Hiragana, other characters are returned as is.
+fn to_hiragana(c: char) -> char {
+    match c {
+        'ァ'..='ヶ' => char::from_u32(u32::from(c) - KATAKANA_OFFSET).unwrap_or(c),
+        _ => c,
+    }
+}
+
+fn romaji(c: char) -> Option<&'static str> {
+    match c {
+        'ヷ' => Some("va"),
+        'ヸ' => Some("vi"),
+        'ヹ' => Some("ve"),
+        'ヺ' => Some("vo"),
+        _ => {
+            let c = to_hiragana(c);
+            if ('ぁ'..='ゖ').contains(&c) {
+                HIRAGANA
+                    .get((u32::from(c) - HIRAGANA_START) as usize)
+                    .copied()
+            } else {
+                None
+            }
+        }
+    }
+}
+
+fn is_small_y(c: char) -> bool {
+    matches!(to_hiragana(c), 'ゃ' | 'ゅ' | 'ょ')
+}
+
+fn is_small_vowel(c: char) -> bool {
+    matches!(to_hiragana(c), 'ぁ' | 'ぃ' | 'ぅ' | 'ぇ' | 'ぉ')
+}
+
+pub fn is_kana(c: char) -> bool {
+    romaji(c).is_some() || c == PROLONGED_SOUND_MARK || c == MIDDLE_DOT
+}
+
+/// Romanizes the kana at the start of `chars`.
+///
+/// Returns the number of characters that were consumed or `None` if `chars` doesn't start with kana.
+pub fn push_romanized(chars: &[char], out: &mut String) -> Option<usize> {
+    let c = *chars.first()?;
+    let next = chars.get(1).copied();
+
+    match to_hiragana(c) {
+        PROLONGED_SOUND_MARK => {
+            // repeat the previous vowel
+            if let Some(vowel) = out.chars().last().filter(|c| "aeiou".contains(*c)) {
+                out.push(vowel);
+            }
+            return Some(1);
+        }
+        MIDDLE_DOT => {
+            out.push(' ');
+            return Some(1);
+        }
+        SMALL_TSU => {
+            // double the next consonant
+            match next.and_then(romaji) {
+                Some(next) if next.starts_with("ch") => out.push('t'),
+                Some(next) => out.extend(next.chars().next().filter(|c| !"aeiou".contains(*c))),
+                None => (),
+            }
+            return Some(1);
+        }
+        _ => (),
+    }
+
+    let base = romaji(c)?;
+    match next {
+        Some(next) if is_small_y(next) && base.len() > 1 && base.ends_with('i') => {
+            let stem = &base[..base.len() - 1];
+            let vowel = &romaji(next).unwrap_or_default()[1..];
+            out.push_str(stem);
+            if !matches!(stem, "sh" | "ch" | "j") {
+                out.push('y');
+            }
+            out.push_str(vowel);
+            Some(2)
+        }
+        Some(next) if is_small_vowel(next) && (base == "u" || base.len() > 1) => {
+            let vowel = romaji(next).unwrap_or_default();
+            if base == "u" {
+                out.push('w');
+            } else {
+                out.push_str(&base[..base.len() - 1]);
+            }
+            out.push_str(vowel);
+            Some(2)
+        }
+        _ => {
+            out.push_str(base);
+            // separate 'n' from a following vowel (e.g. 'kin'en')
+            if base == "n"
+                && next
+                    .and_then(romaji)
+                    .is_some_and(|next| next.starts_with(['a', 'e', 'i', 'o', 'u', 'y']))
+            {
+                out.push('\'');
+            }
+            Some(1)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn romanize_str(s: &str) -> String {
+        let chars: Vec<char> = s.chars().collect();
+        let mut out = String::new();
+        let mut i = 0;
+        while i < chars.len() {
+            if let Some(n) = push_romanized(&chars[i..], &mut out) {
+                i += n;
+            } else {
+                out.push(chars[i]);
+                i += 1;
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn words() {
+        for (kana, expected) in [
+            ("ひらがな", "hiragana"),
+            ("カタカナ", "katakana"),
+            ("とうきょう", "toukyou"),
+            ("しゃしん", "shashin"),
+            ("ちょっと", "chotto"),
+            ("まっちゃ", "matcha"),
+            ("きんえん", "kin'en"),
+            ("こんや", "kon'ya"),
+            ("ラーメン", "raamen"),
+            ("パーティー", "paatii"),
+            ("ファイナル・ファンタジー", "fainaru fantajii"),
+            ("ヴァイオリン", "vaiorin"),
+            ("ジェット", "jetto"),
+            ("ウィンター", "wintaa"),
+            ("夜に駆ける", "夜ni駆keru"),
+        ] {
+            assert_eq!(romanize_str(kana), expected, "{kana}");
+        }
+    }
+}
@@ -0,0 +1,91 @@
+//! Romanization of Japanese kana, Korean Hangul and Cyrillic.
+//!
+//! All tables are bundled, nothing is fetched.
+
+mod cyrillic;
+mod hangul;
+mod kana;
+
+use crate::{config::TransliterationMode, model::PlayInfo};
+use std::borrow::Cow;
+
+/// Romanizes all supported characters in `text`. Other characters are kept.
+pub fn transliterate(text: &str) -> Cow<'_, str> {
+    if !text.chars().any(is_supported) {
+        return Cow::Borrowed(text);
+    }
+
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        if let Some(consumed) = kana::push_romanized(&chars[i..], &mut out) {
+            i += consumed;
+            continue;
+        }
+
+        let c = chars[i];
+        let prev = i.checked_sub(1).map(|i| chars[i]);
+        let next = chars.get(i + 1).copied();
+        if !hangul::push_romanized(c, prev, next, &mut out)
+            && !cyrillic::push_romanized(c, &mut out)
+        {
+            out.push(c);
+        }
+        i += 1;
+    }
+    Cow::Owned(out)
+}
+
+fn is_supported(c: char) -> bool {
+    kana::is_kana(c) || hangul::is_syllable(c) || ('\u{0400}'..='\u{04FF}').contains(&c)
+}
+
+/// Adds `title_latin` and `artist_latin` or replaces the title and artist.
+pub fn apply(info: &mut PlayInfo, mode: TransliterationMode) {
+    match mode {
+        TransliterationMode::Add => {
+            info.title_latin = Some(transliterate(&info.title).into_owned());
+            info.artist_latin = Some(transliterate(&info.artist).into_owned());
+        }
+        TransliterationMode::Replace => {
+            if let Cow::Owned(title) = transliterate(&info.title) {
+                info.title = title;
+            }
+            if let Cow::Owned(artist) = transliterate(&info.artist) {
+                info.artist = artist;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn mixed() {
+        assert!(matches!(
+            transliterate("Never Gonna Give You Up"),
+            Cow::Borrowed("Never Gonna Give You Up")
+        ));
+        assert_eq!(transliterate("アイドル (Idol)"), "aidoru (Idol)");
+        assert_eq!(transliterate("Кино - Группа крови"), "Kino - Gruppa krovi");
+        assert_eq!(transliterate("아이유 - 좋은 날"), "aiyu - joeun nal");
+    }
+
+    #[test]
+    fn apply_modes() {
+        let mut info = PlayInfo::simple("夜に駆ける", "ヨアソビ", "test");
+        apply(&mut info, TransliterationMode::Add);
+        assert_eq!(info.title, "夜に駆ける");
+        assert_eq!(info.title_latin.as_deref(), Some("夜ni駆keru"));
+        assert_eq!(info.artist_latin.as_deref(), Some("yoasobi"));
+
+        let mut info = PlayInfo::simple("Кукушка", "Кино", "test");
+        apply(&mut info, TransliterationMode::Replace);
+        assert_eq!(info.title, "Kukushka");
+        assert_eq!(info.artist, "Kino");
+        assert_eq!(info.title_latin, None);
+    }
+}
@@ -0,0 +1,19 @@
+use std::{ffi::OsString, io, path::Path};
+
+/// Writes `contents` to `path` by writing to a temporary file next to it first
+/// and renaming it afterwards.
+///
+/// Readers (like OBS) will either see the old or the new file, never a partially written one.
+pub async fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let mut tmp_name = OsString::from(".");
+    tmp_name.push(path.file_name().unwrap_or_default());
+    tmp_name.push(".tmp");
+    let tmp_path = path.with_file_name(tmp_name);
+
+    tokio::fs::write(&tmp_path, contents).await?;
+    if let Err(e) = tokio::fs::rename(&tmp_path, path).await {
+        tokio::fs::remove_file(&tmp_path).await.ok();
+        return Err(e);
+    }
+    Ok(())
+}
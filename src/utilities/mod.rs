@@ -1,3 +1,7 @@
 pub mod format_string;
+pub mod fs;
 pub mod serde;
+pub mod tls;
+#[cfg(unix)]
+pub mod unix_socket;
 pub mod websockets;
@@ -0,0 +1,91 @@
+use crate::config::CertificateConfig;
+use rustls::pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer};
+use std::{io, path::Path, sync::Arc};
+use tracing::info;
+
+/// Loads the certificate from the config and builds a rustls config for the listeners.
+///
+/// If neither the certificate nor the key exist and `generate_self_signed` is set,
+/// a self-signed certificate is generated and stored first.
+pub fn load_server_config(config: &CertificateConfig) -> io::Result<rustls::ServerConfig> {
+    let cert_path = CertificateConfig::resolve(&config.cert);
+    let key_path = CertificateConfig::resolve(&config.key);
+    if config.generate_self_signed && !cert_path.exists() && !key_path.exists() {
+        info!(cert = %cert_path.display(), "Generating self-signed certificate");
+        generate_self_signed(&config.subject_alt_names, &cert_path, &key_path)?;
+    }
+
+    let certs = CertificateDer::pem_file_iter(&cert_path)
+        .and_then(Iterator::collect::<Result<Vec<_>, _>>)
+        .map_err(|e| io::Error::other(format!("{}: {e}", cert_path.display())))?;
+    let key = PrivateKeyDer::from_pem_file(&key_path)
+        .map_err(|e| io::Error::other(format!("{}: {e}", key_path.display())))?;
+
+    rustls::ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .and_then(|builder| builder.with_no_client_auth().with_single_cert(certs, key))
+        .map_err(io::Error::other)
+}
+
+fn generate_self_signed(names: &[String], cert_path: &Path, key_path: &Path) -> io::Result<()> {
+    let certified = rcgen::generate_simple_self_signed(names.to_vec()).map_err(io::Error::other)?;
+    if let Some(parent) = cert_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    write_private(key_path, certified.signing_key.serialize_pem().as_bytes())?;
+    std::fs::write(cert_path, certified.cert.pem())
+}
+
+/// Writes a file that's only readable by the current user.
+fn write_private(path: &Path, contents: &[u8]) -> io::Result<()> {
+    use std::io::Write;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)?.write_all(contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generates_and_loads_certificate() {
+        let dir = std::env::temp_dir().join(format!("current-song2-tls-{}", std::process::id()));
+        let config = CertificateConfig {
+            cert: dir.join("cert.pem"),
+            key: dir.join("key.pem"),
+            ..CertificateConfig::default()
+        };
+
+        load_server_config(&config).unwrap();
+        let cert = std::fs::read(&config.cert).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&config.key).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        // an existing certificate is reused
+        load_server_config(&config).unwrap();
+        assert_eq!(std::fs::read(&config.cert).unwrap(), cert);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn missing_certificate() {
+        let dir =
+            std::env::temp_dir().join(format!("current-song2-tls-missing-{}", std::process::id()));
+        let config = CertificateConfig {
+            cert: dir.join("cert.pem"),
+            key: dir.join("key.pem"),
+            generate_self_signed: false,
+            ..CertificateConfig::default()
+        };
+        assert!(load_server_config(&config).is_err());
+    }
+}
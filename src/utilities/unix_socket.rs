@@ -0,0 +1,175 @@
+use std::{
+    fs, io,
+    os::unix::{
+        fs::{DirBuilderExt, FileTypeExt, PermissionsExt},
+        net::{UnixListener, UnixStream},
+    },
+    path::{Path, PathBuf},
+};
+use tracing::info;
+
+/// Binds a Unix domain socket at `path` and restricts its permissions to `mode`.
+///
+/// The socket is bound in a private directory and only moved to `path` after its permissions are set,
+/// so other users can't connect in between.
+/// A socket left behind by a previous run is removed first.
+pub fn bind(path: &Path, mode: u32) -> io::Result<UnixListener> {
+    remove_stale(path)?;
+    let dir = private_dir(path)?;
+    let temp_path = dir.join("s");
+    let result = UnixListener::bind(&temp_path).and_then(|listener| {
+        fs::set_permissions(&temp_path, fs::Permissions::from_mode(mode))?;
+        fs::rename(&temp_path, path)?;
+        Ok(listener)
+    });
+    fs::remove_file(&temp_path).ok();
+    fs::remove_dir(&dir)?;
+    result
+}
+
+/// Creates a directory next to `path` that only the current user can access.
+fn private_dir(path: &Path) -> io::Result<PathBuf> {
+    let name = path.file_name().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} isn't a file path", path.display()),
+        )
+    })?;
+    let mut dir_name = std::ffi::OsString::from(".");
+    dir_name.push(name);
+    dir_name.push(format!(".{}", std::process::id()));
+    let dir = path.with_file_name(dir_name);
+    fs::DirBuilder::new().mode(0o700).create(&dir)?;
+    Ok(dir)
+}
+
+/// Removes the socket at `path` if no one is listening on it anymore.
+///
+/// Fails if `path` isn't a socket or another process is still listening.
+fn remove_stale(path: &Path) -> io::Result<()> {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    if !metadata.file_type().is_socket() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} exists and is not a socket", path.display()),
+        ));
+    }
+    match UnixStream::connect(path) {
+        Ok(_) => Err(io::Error::new(
+            io::ErrorKind::AddrInUse,
+            format!("{} is in use by another process", path.display()),
+        )),
+        Err(e) if e.kind() == io::ErrorKind::ConnectionRefused => {
+            info!(path = %path.display(), "Removing stale socket");
+            fs::remove_file(path)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        actors::manager::Update,
+        config::{AuthConfig, RouteGroup},
+        repositories::{init_repositories, RestEvents},
+    };
+    use actix_web::{web, App, HttpServer};
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        sync::watch,
+    };
+
+    fn socket_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("current-song2-{name}-{}.sock", std::process::id()))
+    }
+
+    #[test]
+    fn stale_sockets() {
+        let path = socket_path("stale");
+        drop(UnixListener::bind(&path).unwrap());
+        assert!(path.exists());
+
+        let listener = bind(&path, 0o600).unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert!(
+            !private_dir_path(&path).exists(),
+            "the private directory is removed"
+        );
+
+        // still in use
+        let err = bind(&path, 0o600).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AddrInUse);
+        drop(listener);
+        fs::remove_file(&path).unwrap();
+
+        // regular files are never removed
+        fs::write(&path, "data").unwrap();
+        let err = bind(&path, 0o600).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        fs::remove_file(&path).unwrap();
+    }
+
+    fn private_dir_path(path: &Path) -> PathBuf {
+        let name = path.file_name().unwrap().to_str().unwrap();
+        path.with_file_name(format!(".{name}.{}", std::process::id()))
+    }
+
+    async fn get(path: &Path, uri: &str, token: Option<&str>) -> String {
+        let mut stream = tokio::net::UnixStream::connect(path).await.unwrap();
+        let auth = token.map_or(String::new(), |token| {
+            format!("Authorization: Bearer {token}\r\n")
+        });
+        stream
+            .write_all(
+                format!("GET {uri} HTTP/1.1\r\nHost: localhost\r\n{auth}Connection: close\r\n\r\n")
+                    .as_bytes(),
+            )
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[actix_web::test]
+    async fn serves_api() {
+        let (_tx, rx) = watch::channel(Update::initial());
+        let events = web::Data::new(RestEvents(rx));
+        let auth = web::Data::new(AuthConfig {
+            display: Some("display-token".to_owned()),
+            ..AuthConfig::default()
+        });
+
+        let path = socket_path("api");
+        let srv = HttpServer::new(move || {
+            App::new()
+                .app_data(events.clone())
+                .app_data(auth.clone())
+                .service(web::scope("api").configure(|config| {
+                    init_repositories(config, &RouteGroup::all());
+                }))
+        })
+        .workers(1)
+        .listen_uds(bind(&path, 0o600).unwrap())
+        .unwrap()
+        .run();
+        let handle = srv.handle();
+        actix_web::rt::spawn(srv);
+
+        let response = get(&path, "/api/state", None).await;
+        assert!(response.starts_with("HTTP/1.1 401"), "{response}");
+        let response = get(&path, "/api/state", Some("display-token")).await;
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+        assert!(response.ends_with(r#"{"type":"Paused"}"#), "{response}");
+
+        handle.stop(true).await;
+        fs::remove_file(&path).ok();
+    }
+}
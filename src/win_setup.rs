@@ -99,12 +99,19 @@ pub fn should_replace_invalid_config(loc: &Path, err: &impl std::fmt::Display) -
 
 fn fmt_instance_id() -> String {
     let mut base = "current-song2::main-executable::".to_owned();
-    let _ = match &CONFIG.server.bind {
-        config::BindConfig::Single { port } => std::fmt::write(&mut base, format_args!("{port}")),
-        config::BindConfig::Multiple { bind } => {
-            std::fmt::write(&mut base, format_args!("{bind:?}"))
+    for (i, listener) in CONFIG.server.listeners().iter().enumerate() {
+        if i > 0 {
+            base.push(',');
         }
-    };
+        let _ = match &listener.bind {
+            config::BindConfig::Single { port, .. } => {
+                std::fmt::write(&mut base, format_args!("{port}"))
+            }
+            config::BindConfig::Multiple { bind } => {
+                std::fmt::write(&mut base, format_args!("{bind:?}"))
+            }
+        };
+    }
     base
 }
 
@@ -0,0 +1,294 @@
+use crate::{
+    config::{CoverFormat, CoverOutputConfig},
+    image_store::ImageStore,
+    manager,
+    model::{ImageInfo, ModuleState},
+    utilities::fs::write_atomic,
+};
+use image::{DynamicImage, ImageFormat};
+use std::{
+    io::Cursor,
+    sync::{Arc, RwLock},
+};
+use tap::TapFallible;
+use tokio::sync::watch;
+use tracing::{debug, info, warn};
+
+struct Cover {
+    content_type: String,
+    data: Vec<u8>,
+}
+
+impl CoverFormat {
+    fn content_type(self) -> &'static str {
+        match self {
+            CoverFormat::Png => "image/png",
+            CoverFormat::Jpeg => "image/jpeg",
+            CoverFormat::Webp => "image/webp",
+        }
+    }
+}
+
+pub async fn output_cover_to_file(
+    config: &CoverOutputConfig,
+    image_store: Arc<RwLock<ImageStore>>,
+    mut rx: watch::Receiver<manager::Event>,
+) {
+    let path = config.path.as_path();
+    debug!(path = ?path, "Enabled cover output");
+
+    let placeholder = load_placeholder(config).await;
+    // `Some(None)` means the placeholder was written last
+    let mut last_written: Option<Option<(usize, usize)>> = None;
+
+    loop {
+        let image = current_image(&rx.borrow_and_update().state);
+
+        // an image is never changed once it's stored, so the bytes only need to be read for a new one
+        if last_written != Some(image) {
+            let cover = image.and_then(|(id, epoch_id)| read_cover(id, epoch_id, &image_store));
+            let data = match cover {
+                Some(cover) => {
+                    let format = config.format;
+                    tokio::task::spawn_blocking(move || convert(cover, format))
+                        .await
+                        .map_err(anyhow::Error::from)
+                        .and_then(|res| res)
+                        .tap_err(|e| warn!(error = %e, "Couldn't convert cover art"))
+                        .ok()
+                }
+                None => None,
+            };
+
+            let res = match data {
+                Some(ref data) => write_atomic(path, data).await,
+                None => write_atomic(path, &placeholder).await,
+            };
+            match res {
+                Ok(()) => last_written = Some(image.filter(|_| data.is_some())),
+                Err(e) => warn!(error = %e, "Couldn't write cover art"),
+            }
+        }
+
+        if rx.changed().await.is_err() {
+            break;
+        }
+    }
+    info!("Channel closed - Stopped cover output");
+}
+
+/// Gets the `(id, epoch_id)` of the image of the currently playing song.
+///
+/// External images aren't fetched.
+fn current_image(state: &ModuleState) -> Option<(usize, usize)> {
+    let ModuleState::Playing(info) = state else {
+        return None;
+    };
+    match info.image {
+        Some(ImageInfo::Internal(ref internal)) => Some((internal.id, internal.epoch_id)),
+        Some(ImageInfo::External(ref url)) => {
+            debug!(url, "Not writing external cover art");
+            None
+        }
+        None => None,
+    }
+}
+
+fn read_cover(id: usize, epoch_id: usize, image_store: &RwLock<ImageStore>) -> Option<Cover> {
+    let store = image_store.read().unwrap();
+    store.get(id, epoch_id).map(|image| Cover {
+        content_type: image.content_type.clone(),
+        data: image.data.clone(),
+    })
+}
+
+async fn load_placeholder(config: &CoverOutputConfig) -> Vec<u8> {
+    if let Some(ref placeholder) = config.placeholder {
+        match tokio::fs::read(placeholder).await {
+            Ok(data) => {
+                let cover = Cover {
+                    content_type: String::new(),
+                    data,
+                };
+                match convert(cover, config.format) {
+                    Ok(data) => return data,
+                    Err(e) => warn!(error = %e, "Couldn't convert the placeholder"),
+                }
+            }
+            Err(e) => {
+                warn!(error = %e, path = ?placeholder, "Couldn't read the placeholder");
+            }
+        }
+    }
+
+    encode(
+        &DynamicImage::new_rgba8(1, 1),
+        config.format.unwrap_or(CoverFormat::Png),
+    )
+    .unwrap_or_default()
+}
+
+fn convert(cover: Cover, format: Option<CoverFormat>) -> anyhow::Result<Vec<u8>> {
+    match format {
+        Some(format) if cover.content_type != format.content_type() => {
+            encode(&image::load_from_memory(&cover.data)?, format)
+        }
+        _ => Ok(cover.data),
+    }
+}
+
+fn encode(image: &DynamicImage, format: CoverFormat) -> anyhow::Result<Vec<u8>> {
+    let mut buf = Cursor::new(Vec::new());
+    match format {
+        // JPEG doesn't support transparency
+        CoverFormat::Jpeg => {
+            DynamicImage::from(image.to_rgb8()).write_to(&mut buf, ImageFormat::Jpeg)?;
+        }
+        CoverFormat::Png => image.write_to(&mut buf, ImageFormat::Png)?,
+        // the WebP encoder only supports RGB(A)8
+        CoverFormat::Webp => {
+            DynamicImage::from(image.to_rgba8()).write_to(&mut buf, ImageFormat::WebP)?;
+        }
+    }
+    Ok(buf.into_inner())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        model::{InternalImage, PlayInfo},
+        workers::delay::delayed,
+    };
+    use std::time::Duration;
+
+    fn playing(seq: u64, id: usize, epoch_id: usize) -> manager::Event {
+        let mut info = PlayInfo::simple("Title", "Artist", "test");
+        info.image = Some(ImageInfo::Internal(InternalImage { id, epoch_id }));
+        manager::Update::with_state(seq, ModuleState::Playing(info))
+    }
+
+    async fn wait_for_file(path: &std::path::Path, expected: &[u8]) {
+        for _ in 0..100 {
+            if tokio::fs::read(path)
+                .await
+                .is_ok_and(|data| data == expected)
+            {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("{path:?} didn't contain {expected:?}");
+    }
+
+    #[tokio::test]
+    async fn changes_cover_while_delayed() {
+        let dir = std::env::temp_dir().join(format!("cso2-cover-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let config = CoverOutputConfig {
+            path: dir.join("cover.png"),
+            ..Default::default()
+        };
+        let path = config.path.clone();
+
+        let image_store = Arc::new(RwLock::new(
+            ImageStore::new().with_retention(Duration::from_secs(1)),
+        ));
+        let slot = image_store.write().unwrap().create_id();
+        let (tx, rx) = watch::channel(manager::Update::initial());
+        let delayed_rx = delayed(rx, Duration::from_millis(200));
+        tokio::spawn({
+            let image_store = image_store.clone();
+            async move { output_cover_to_file(&config, image_store, delayed_rx).await }
+        });
+
+        let first =
+            image_store
+                .write()
+                .unwrap()
+                .store(slot, "image/png".to_owned(), b"first".to_vec());
+        tx.send(playing(1, slot, first)).unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        // the next track replaces the cover before the first one was written
+        let second =
+            image_store
+                .write()
+                .unwrap()
+                .store(slot, "image/png".to_owned(), b"second".to_vec());
+        tx.send(playing(2, slot, second)).unwrap();
+
+        wait_for_file(&path, b"first").await;
+        wait_for_file(&path, b"second").await;
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn skips_unchanged_covers() {
+        let dir = std::env::temp_dir().join(format!("cso2-cover-skip-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let config = CoverOutputConfig {
+            path: dir.join("cover.png"),
+            ..Default::default()
+        };
+        let path = config.path.clone();
+
+        let image_store = Arc::new(RwLock::new(ImageStore::new()));
+        let slot = image_store.write().unwrap().create_id();
+        let (tx, rx) = watch::channel(manager::Update::initial());
+        tokio::spawn({
+            let image_store = image_store.clone();
+            async move { output_cover_to_file(&config, image_store, rx).await }
+        });
+
+        let epoch =
+            image_store
+                .write()
+                .unwrap()
+                .store(slot, "image/png".to_owned(), b"cover".to_vec());
+        tx.send(playing(1, slot, epoch)).unwrap();
+        wait_for_file(&path, b"cover").await;
+
+        // the store isn't read again for the same image (it would fall back to the placeholder)
+        image_store.write().unwrap().remove(slot);
+        tx.send(playing(2, slot, epoch)).unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(std::fs::read(&path).unwrap(), b"cover");
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn converts() {
+        let png = encode(&DynamicImage::new_rgba8(2, 2), CoverFormat::Png).unwrap();
+        assert_eq!(
+            image::guess_format(&png).unwrap(),
+            ImageFormat::Png,
+            "encoded as png"
+        );
+
+        let cover = Cover {
+            content_type: "image/png".to_owned(),
+            data: png.clone(),
+        };
+        assert_eq!(convert(cover, Some(CoverFormat::Png)).unwrap(), png);
+
+        let cover = Cover {
+            content_type: "image/png".to_owned(),
+            data: png.clone(),
+        };
+        assert_eq!(convert(cover, None).unwrap(), png);
+
+        for (format, expected) in [
+            (CoverFormat::Jpeg, ImageFormat::Jpeg),
+            (CoverFormat::Webp, ImageFormat::WebP),
+        ] {
+            let cover = Cover {
+                content_type: "image/png".to_owned(),
+                data: png.clone(),
+            };
+            let converted = convert(cover, Some(format)).unwrap();
+            assert_eq!(image::guess_format(&converted).unwrap(), expected);
+        }
+    }
+}
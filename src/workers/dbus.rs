@@ -1,18 +1,21 @@
-use std::{ffi::OsStr, path::Path, sync::Arc};
+use std::{collections::HashMap, ffi::OsStr, path::Path, sync::Arc, time::Duration};
 
 use crate::{
-    actors::manager::{CreateModule, Manager, RemoveModule, UpdateModule},
-    config::CONFIG,
+    actors::manager::{
+        ControlAction, ControlError, Controller, CreateModule, Manager, RemoveModule,
+        SetController, UpdateModule,
+    },
+    config::DbusConfig,
     image_store::{ImageStore, SlotRef},
     model::{AlbumInfo, ImageInfo, InternalImage, ModuleState, PlayInfo, TimelineInfo},
 };
 use actix::Addr;
 use anyhow::Result as AnyResult;
-use futures::StreamExt;
-use mpris_dbus::{interface::PlaybackStatus, player};
+use futures::{future::BoxFuture, StreamExt};
+use mpris_dbus::{discovery::NameEvent, interface::PlaybackStatus, player, PlayerRegistry};
 use std::sync::RwLock;
 use tap::TapFallible;
-use tokio::sync::mpsc;
+use tokio::{sync::mpsc, task::JoinHandle};
 use tracing::{debug, info, span, warn, Instrument, Level};
 use url::Url;
 
@@ -26,63 +29,155 @@ struct DBusWorker {
     image_id: SlotRef,
 }
 
-pub async fn start_spawning(
+/// A worker listening to a single player.
+struct Worker {
+    module_id: usize,
+    task: JoinHandle<()>,
+}
+
+impl Worker {
+    /// Stops listening to the player. This drops the [`SlotRef`] and removes the module.
+    fn stop(self, manager: &Addr<Manager>) {
+        self.task.abort();
+        manager.do_send(RemoveModule { id: self.module_id });
+    }
+}
+
+/// The delay before reconnecting to the session bus, doubled after every failed attempt.
+const MIN_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_mins(1);
+
+/// Listens to the players matching `config` in the background.
+///
+/// If the session bus isn't available or the connection is lost, it reconnects with a backoff.
+pub fn start_spawning(
+    config: &'static DbusConfig,
     manager: Addr<Manager>,
     image_store: Arc<RwLock<ImageStore>>,
-) -> AnyResult<()> {
-    let discoverer = mpris_dbus::discovery::Listener::new().await?;
-    let mut name_stream = discoverer.listen().await?;
-    tokio::spawn(async move {
-        while let Some(name) = name_stream.next().await {
-            if !CONFIG
-                .modules
-                .dbus
-                .destinations
-                .iter()
-                .any(|s| fast_glob::glob_match(s.as_bytes(), name.as_bytes()))
-            {
-                debug!("Ignoring {}", name);
-                continue;
+) {
+    tokio::spawn(
+        async move {
+            let mut delay = MIN_RECONNECT_DELAY;
+            loop {
+                match discover(config, &manager, &image_store).await {
+                    Ok(()) => {
+                        warn!("Lost the connection to the session bus");
+                        delay = MIN_RECONNECT_DELAY;
+                    }
+                    Err(e) => warn!(error = %e, "Failed to listen to the session bus"),
+                }
+                info!("Reconnecting in {:?}", delay);
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(MAX_RECONNECT_DELAY);
             }
-            info!("Listening to dbus service {}", name);
+        }
+        .instrument(span!(Level::INFO, "DBusSupervisor")),
+    );
+}
 
-            let manager = manager.clone();
-            let Ok(module_id) = manager
-                .send(CreateModule { priority: 0 })
-                .await
-                .tap_err(|e| warn!(error = %e, "Failed to create module"))
-            else {
-                continue;
-            };
-            let image_store = image_store.clone();
-            let image_id = SlotRef::new(&image_store);
-            let source = name.clone();
-            tokio::spawn(
-                async move {
-                    let worker = DBusWorker {
-                        manager,
-                        module_id,
-                        paused: false,
-                        source,
-                        image_store,
-                        image_id,
-                    };
-                    let Ok(rx) = player::listen(worker.source.clone())
-                        .await
-                        .tap_err(|e| warn!(error = %e, "Failed to listen"))
-                    else {
-                        return;
-                    };
-                    worker.feed_manager(rx).await;
+/// Spawns a worker for every player until the connection to the session bus is lost.
+async fn discover(
+    config: &'static DbusConfig,
+    manager: &Addr<Manager>,
+    image_store: &Arc<RwLock<ImageStore>>,
+) -> AnyResult<()> {
+    let registry = PlayerRegistry::session().await?;
+    let mut name_stream = Box::pin(registry.names().await?);
+    let mut workers: HashMap<_, Worker> = HashMap::new();
+    while let Some(event) = name_stream.next().await {
+        let name = match event {
+            NameEvent::Appeared(name) => name,
+            NameEvent::Vanished(name) => {
+                if let Some(worker) = workers.remove(&name) {
+                    info!("Dbus service {} vanished", name);
+                    worker.stop(manager);
                 }
-                .instrument(span!(Level::INFO, "DBusWorker", source = %name)),
-            );
+                continue;
+            }
+        };
+        if !config
+            .destinations
+            .iter()
+            .any(|s| fast_glob::glob_match(s.as_bytes(), name.as_bytes()))
+        {
+            debug!("Ignoring {}", name);
+            continue;
         }
-    });
+        // names acquired while discovery started are reported twice
+        if workers.contains_key(&name) {
+            continue;
+        }
+        info!("Listening to dbus service {}", name);
+
+        let Ok(module_id) = manager
+            .send(CreateModule { priority: 0 })
+            .await
+            .tap_err(|e| warn!(error = %e, "Failed to create module"))
+        else {
+            continue;
+        };
+        let worker = DBusWorker {
+            manager: manager.clone(),
+            module_id,
+            paused: false,
+            source: name.clone(),
+            image_id: SlotRef::new(image_store),
+            image_store: image_store.clone(),
+        };
+        let registry = registry.clone();
+        let task = tokio::spawn(
+            async move {
+                let Ok((rx, controls)) = registry
+                    .listen(worker.source.clone())
+                    .await
+                    .tap_err(|e| warn!(error = %e, "Failed to listen"))
+                else {
+                    return;
+                };
+                worker.manager.do_send(SetController {
+                    id: worker.module_id,
+                    controller: Box::new(controls),
+                });
+                worker.feed_manager(rx).await;
+            }
+            .instrument(span!(Level::INFO, "DBusWorker", source = %name)),
+        );
+        workers.insert(name, Worker { module_id, task });
+    }
 
+    for worker in workers.into_values() {
+        worker.stop(manager);
+    }
     Ok(())
 }
 
+impl Controller for player::Controls {
+    fn control(&self, action: ControlAction) -> BoxFuture<'static, Result<(), ControlError>> {
+        let command = match action {
+            ControlAction::Play => player::Command::Play,
+            ControlAction::Pause => player::Command::Pause,
+            ControlAction::Toggle => player::Command::PlayPause,
+            ControlAction::Next => player::Command::Next,
+            ControlAction::Previous => player::Command::Previous,
+            ControlAction::Seek { offset_ms } => {
+                player::Command::Seek(offset_ms.saturating_mul(1000))
+            }
+            ControlAction::SeekTo { position_ms } => player::Command::SeekTo(
+                i64::try_from(position_ms)
+                    .unwrap_or(i64::MAX)
+                    .saturating_mul(1000),
+            ),
+        };
+        let controls = self.clone();
+        Box::pin(async move {
+            controls.execute(command).await.map_err(|e| match e {
+                player::ControlError::NotSupported => ControlError::Unsupported,
+                player::ControlError::Failed(e) => ControlError::Failed(e.to_string()),
+            })
+        })
+    }
+}
+
 impl DBusWorker {
     async fn feed_manager(mut self, mut rx: mpsc::Receiver<player::State>) {
         while let Some(evt) = rx.recv().await {
@@ -148,6 +243,8 @@ impl DBusWorker {
                 track_count: 0,
             }),
             source: format!("dbus::{}", self.source),
+            title_latin: None,
+            artist_latin: None,
         });
     }
 
@@ -196,3 +293,100 @@ impl DBusWorker {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actors::manager::{ListModules, ModuleInfo, Update};
+    use actix::Actor;
+    use mpris_dbus::testing::{Bus, MockPlayer};
+    use tokio::sync::watch;
+
+    const NAME: &str = "org.mpris.MediaPlayer2.mock";
+
+    async fn wait_for_modules(
+        manager: &Addr<Manager>,
+        f: impl Fn(&[ModuleInfo]) -> bool,
+    ) -> Vec<ModuleInfo> {
+        for _ in 0..250 {
+            let modules = manager.send(ListModules).await.unwrap();
+            if f(&modules) {
+                return modules;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!(
+            "Modules didn't change {:?}",
+            manager.send(ListModules).await.unwrap()
+        );
+    }
+
+    fn is_playing(modules: &[ModuleInfo]) -> bool {
+        modules.len() == 1 && matches!(modules[0].state, ModuleState::Playing(_))
+    }
+
+    #[actix::test]
+    async fn removes_vanished_players() {
+        let Some(bus) = Bus::start() else {
+            return;
+        };
+        let (event_tx, _rx) = watch::channel(Update::initial());
+        let manager = Manager::new(event_tx).start();
+        let image_store = Arc::new(RwLock::new(ImageStore::new()));
+        let config = Box::leak(Box::new(DbusConfig {
+            enabled: true,
+            destinations: vec![NAME.to_owned()],
+        }));
+        let player = MockPlayer {
+            status: "Playing",
+            title: "Title".to_owned(),
+            ..MockPlayer::default()
+        };
+
+        let conn = bus.start_player(NAME, player.clone()).await;
+        start_spawning(config, manager.clone(), image_store);
+        let first = wait_for_modules(&manager, is_playing).await;
+
+        conn.close().await.unwrap();
+        wait_for_modules(&manager, <[_]>::is_empty).await;
+
+        // the player comes back with a new module
+        let _conn = bus.start_player(NAME, player).await;
+        let second = wait_for_modules(&manager, is_playing).await;
+        assert_ne!(first[0].id, second[0].id);
+    }
+
+    #[actix::test]
+    async fn reconnects_to_the_bus() {
+        let Some(mut bus) = Bus::start() else {
+            return;
+        };
+        // no session bus at all
+        bus.stop();
+        let (event_tx, _rx) = watch::channel(Update::initial());
+        let manager = Manager::new(event_tx).start();
+        let image_store = Arc::new(RwLock::new(ImageStore::new()));
+        let config = Box::leak(Box::new(DbusConfig {
+            enabled: true,
+            destinations: vec![NAME.to_owned()],
+        }));
+        let player = MockPlayer {
+            status: "Playing",
+            title: "Title".to_owned(),
+            ..MockPlayer::default()
+        };
+        start_spawning(config, manager.clone(), image_store);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        bus.restart();
+        let _conn = bus.start_player(NAME, player.clone()).await;
+        wait_for_modules(&manager, is_playing).await;
+
+        // the modules are removed when the connection is lost
+        bus.restart();
+        wait_for_modules(&manager, <[_]>::is_empty).await;
+
+        let _conn = bus.start_player(NAME, player).await;
+        wait_for_modules(&manager, is_playing).await;
+    }
+}
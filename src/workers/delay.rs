@@ -0,0 +1,151 @@
+use crate::{
+    manager::{self, EventKind},
+    model::{ModuleState, PlayInfo, TimelineInfo},
+};
+use std::{collections::VecDeque, sync::Arc, time::Duration};
+use tokio::{sync::watch, time::Instant};
+use tracing::{debug, info};
+
+/// Returns a receiver which gets all events from `rx` after `delay`.
+///
+/// The timestamps of timelines are shifted by the delay,
+/// so the progress computed from them matches the delayed output.
+/// If `delay` is zero, `rx` is returned as is.
+pub fn delayed(
+    rx: watch::Receiver<manager::Event>,
+    delay: Duration,
+) -> watch::Receiver<manager::Event> {
+    if delay.is_zero() {
+        return rx;
+    }
+    let (tx, delayed_rx) = watch::channel(shift(&rx.borrow(), delay));
+    tokio::spawn(forward(rx, tx, delay));
+    delayed_rx
+}
+
+async fn forward(
+    mut rx: watch::Receiver<manager::Event>,
+    tx: watch::Sender<manager::Event>,
+    delay: Duration,
+) {
+    debug!(?delay, "Started delay");
+    let mut queue: VecDeque<(Instant, manager::Event)> = VecDeque::new();
+    let mut source_open = true;
+    // queued events are still released after the source closed
+    while source_open || !queue.is_empty() {
+        let next_release = queue.front().map(|(at, _)| *at);
+        tokio::select! {
+            changed = rx.changed(), if source_open => {
+                if changed.is_err() {
+                    source_open = false;
+                    continue;
+                }
+                let event = shift(&rx.borrow_and_update(), delay);
+                queue.push_back((Instant::now() + delay, event));
+            }
+            () = tokio::time::sleep_until(next_release.unwrap_or_else(Instant::now)), if next_release.is_some() => {
+                if let Some((_, event)) = queue.pop_front() {
+                    if tx.send(event).is_err() {
+                        break;
+                    }
+                }
+            }
+            () = tx.closed() => break,
+        }
+    }
+    info!("Channel closed - Stopped delay");
+}
+
+fn shift(event: &manager::Event, delay: Duration) -> manager::Event {
+    let delay_ms = delay.as_millis().try_into().unwrap_or(u64::MAX);
+    let shift_timeline = |timeline: &mut TimelineInfo| {
+        timeline.ts = timeline.ts.saturating_add(delay_ms);
+    };
+    let shift_info = |info: &mut PlayInfo| {
+        if let Some(ref mut timeline) = info.timeline {
+            shift_timeline(timeline);
+        }
+    };
+
+    let mut update = manager::Update::clone(event);
+    if let ModuleState::Playing(ref info) = *update.state {
+        if info.timeline.is_some() {
+            let mut info = info.clone();
+            shift_info(&mut info);
+            update.state = Arc::new(ModuleState::Playing(info));
+        }
+    }
+    for kind in &mut update.transitions {
+        match kind {
+            EventKind::TrackChanged(info) => shift_info(info),
+            EventKind::Seeked(Some(timeline))
+            | EventKind::RateChanged(timeline)
+            | EventKind::Resumed(Some(timeline)) => shift_timeline(timeline),
+            EventKind::Seeked(None)
+            | EventKind::Resumed(None)
+            | EventKind::ArtChanged(_)
+            | EventKind::Paused => (),
+        }
+    }
+    Arc::new(update)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn playing(seq: u64, info: &PlayInfo) -> manager::Update {
+        manager::Update {
+            seq,
+            state: Arc::new(ModuleState::Playing(info.clone())),
+            transitions: vec![
+                EventKind::Resumed(info.timeline.clone()),
+                EventKind::TrackChanged(info.clone()),
+            ],
+        }
+    }
+
+    #[tokio::test]
+    async fn delays_events() {
+        let (tx, rx) = watch::channel(manager::Update::initial());
+        let mut delayed_rx = delayed(rx, Duration::from_millis(100));
+        assert_eq!(*delayed_rx.borrow_and_update().state, ModuleState::Paused);
+
+        let start = Instant::now();
+        let mut info = PlayInfo::simple("Title", "Artist", "test");
+        info.timeline = Some(TimelineInfo {
+            ts: 1000,
+            duration_ms: 10_000,
+            progress_ms: 500,
+            rate: 1.0,
+        });
+        tx.send(Arc::new(playing(1, &info))).unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let paused = manager::Update {
+            seq: 2,
+            state: Arc::new(ModuleState::Paused),
+            transitions: vec![EventKind::Paused],
+        };
+        tx.send(Arc::new(paused.clone())).unwrap();
+
+        // the timelines of the state and the transitions are shifted
+        delayed_rx.changed().await.unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(100));
+        info.timeline.as_mut().unwrap().ts = 1100;
+        assert_eq!(**delayed_rx.borrow_and_update(), playing(1, &info));
+
+        delayed_rx.changed().await.unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(120));
+        assert_eq!(**delayed_rx.borrow_and_update(), paused);
+
+        drop(tx);
+        assert!(delayed_rx.changed().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn no_delay() {
+        let (tx, rx) = watch::channel(manager::Update::initial());
+        let delayed_rx = delayed(rx, Duration::ZERO);
+        assert!(delayed_rx.same_channel(&tx.subscribe()));
+    }
+}
@@ -0,0 +1,265 @@
+use super::Interpolation;
+use crate::{
+    config::{FileOutputConfig, FileOutputMode, TextEncoding},
+    manager,
+    model::{ModuleState, PlayInfo},
+    utilities::format_string::InterpolationProvider,
+};
+use chrono::{SecondsFormat, Utc};
+use serde::Serialize;
+use std::{
+    ffi::OsString,
+    io,
+    path::{Path, PathBuf},
+};
+use tap::TapFallible;
+use tokio::{fs, io::AsyncWriteExt, sync::watch};
+use tracing::{debug, info, warn};
+
+/// An append-only file which is rotated once it exceeds a size.
+struct RotatingFile {
+    path: PathBuf,
+    max_size: u64,
+    max_backups: usize,
+    encoding: TextEncoding,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct NdjsonEntry<'a> {
+    ts: i64,
+    #[serde(flatten)]
+    info: &'a PlayInfo,
+}
+
+enum LogFormat {
+    Ndjson,
+    Csv { columns: Vec<Interpolation> },
+}
+
+/// Appends a line to the log in `config.path` whenever the track changes.
+pub async fn output_log(config: &FileOutputConfig, mut rx: watch::Receiver<manager::Event>) {
+    let mut file = RotatingFile {
+        path: config.path.clone(),
+        max_size: config.max_size,
+        max_backups: config.max_backups,
+        encoding: TextEncoding::Utf8,
+    };
+    let (format, header) = match config.mode {
+        FileOutputMode::CsvLog => {
+            let (columns, header) = csv_columns(&config.columns);
+            file.encoding = config.encoding;
+            (LogFormat::Csv { columns }, Some(header))
+        }
+        _ => (LogFormat::Ndjson, None),
+    };
+
+    let mut last_track = None;
+    while rx.changed().await.is_ok() {
+        let line = {
+            let state = rx.borrow();
+            let ModuleState::Playing(ref info) = *state.state else {
+                continue;
+            };
+            let track = track_of(info);
+            if last_track.as_ref() == Some(&track) {
+                continue;
+            }
+            last_track = Some(track);
+            format.line(info)
+        };
+        let Some(line) = line else {
+            continue;
+        };
+
+        if let Err(e) = file.append(&line, header.as_deref()).await {
+            warn!(error = %e, "Couldn't append to log");
+        }
+    }
+    info!("Channel closed - Stopped log output");
+}
+
+/// Parses the CSV columns and returns them with the header.
+///
+/// Invalid columns are skipped (and left out of the header).
+fn csv_columns(names: &[String]) -> (Vec<Interpolation>, String) {
+    let (columns, names): (Vec<_>, Vec<_>) = names
+        .iter()
+        .filter_map(|name| {
+            Interpolation::parse_provider(name)
+                .tap_err(|e| warn!(error = %e, "Invalid column"))
+                .ok()
+                .map(|column| (column, name.as_str()))
+        })
+        .unzip();
+    let header = csv_line(std::iter::once("timestamp").chain(names));
+    (columns, header)
+}
+
+/// Identifies a track, changes in the timeline or image are ignored.
+fn track_of(info: &PlayInfo) -> (String, String, Option<String>) {
+    (
+        info.title.clone(),
+        info.artist.clone(),
+        info.album.as_ref().map(|a| a.title.clone()),
+    )
+}
+
+impl LogFormat {
+    fn line(&self, info: &PlayInfo) -> Option<String> {
+        match self {
+            LogFormat::Ndjson => {
+                let entry = NdjsonEntry {
+                    ts: Utc::now().timestamp_millis(),
+                    info,
+                };
+                serde_json::to_string(&entry)
+                    .tap_err(|e| warn!(error = %e, "Cannot serialize json"))
+                    .ok()
+                    .map(|mut line| {
+                        line.push('\n');
+                        line
+                    })
+            }
+            LogFormat::Csv { columns } => {
+                let timestamp = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+                let values: Vec<String> = columns
+                    .iter()
+                    .map(|column| {
+                        let mut value = String::new();
+                        column.format(info, &mut value).ok();
+                        value
+                    })
+                    .collect();
+                Some(csv_line(
+                    std::iter::once(timestamp.as_str()).chain(values.iter().map(String::as_str)),
+                ))
+            }
+        }
+    }
+}
+
+/// Formats a CSV line according to RFC 4180 (including the trailing line break).
+fn csv_line<'a>(fields: impl IntoIterator<Item = &'a str>) -> String {
+    let mut line = String::new();
+    for (i, field) in fields.into_iter().enumerate() {
+        if i > 0 {
+            line.push(',');
+        }
+        if field.contains([',', '"', '\n', '\r']) {
+            line.push('"');
+            line.push_str(&field.replace('"', "\"\""));
+            line.push('"');
+        } else {
+            line.push_str(field);
+        }
+    }
+    line.push_str("\r\n");
+    line
+}
+
+impl RotatingFile {
+    /// Appends `line` in a single write.
+    /// Unlike the other outputs, this doesn't replace the file, so it relies on `O_APPEND`
+    /// writes not being interleaved (a reader may still see a partial line).
+    /// `header` (and the BOM) is written before the line if the file is empty.
+    async fn append(&self, line: &str, header: Option<&str>) -> io::Result<()> {
+        let mut size = fs::metadata(&self.path).await.map_or(0, |m| m.len());
+        let mut contents = self.encoding.encode(line, false);
+        if size > 0 && size + contents.len() as u64 > self.max_size {
+            debug!(path = ?self.path, size, "Rotating log");
+            self.rotate().await?;
+            size = 0;
+        }
+
+        if size == 0 {
+            let mut start = self.encoding.encode(header.unwrap_or_default(), true);
+            start.append(&mut contents);
+            contents = start;
+        }
+
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await?;
+        file.write_all(&contents).await?;
+        file.flush().await
+    }
+
+    /// Moves `path` to `path.1`, `path.1` to `path.2` and so on.
+    async fn rotate(&self) -> io::Result<()> {
+        if self.max_backups == 0 {
+            return fs::remove_file(&self.path).await;
+        }
+        for i in (1..self.max_backups).rev() {
+            let from = backup_path(&self.path, i);
+            if fs::try_exists(&from).await.unwrap_or_default() {
+                fs::rename(from, backup_path(&self.path, i + 1)).await?;
+            }
+        }
+        fs::rename(&self.path, backup_path(&self.path, 1)).await
+    }
+}
+
+fn backup_path(path: &Path, n: usize) -> PathBuf {
+    let mut name = OsString::from(path.file_name().unwrap_or_default());
+    name.push(format!(".{n}"));
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn csv_escaping() {
+        assert_eq!(csv_line(["a", "b", ""]), "a,b,\r\n");
+        assert_eq!(
+            csv_line(["Artist, Other", "\"Quoted\"", "multi\nline"]),
+            "\"Artist, Other\",\"\"\"Quoted\"\"\",\"multi\nline\"\r\n"
+        );
+    }
+
+    #[test]
+    fn invalid_columns() {
+        let names = ["title", "tilte", "artist"].map(String::from);
+        let (columns, header) = csv_columns(&names);
+        assert_eq!(header, "timestamp,title,artist\r\n");
+
+        let format = LogFormat::Csv { columns };
+        let line = format
+            .line(&PlayInfo::simple("Title", "Artist", "test"))
+            .unwrap();
+        assert!(line.ends_with(",Title,Artist\r\n"), "{line}");
+    }
+
+    #[tokio::test]
+    async fn rotation() {
+        let dir = std::env::temp_dir().join(format!("cso2-log-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = RotatingFile {
+            path: dir.join("history.csv"),
+            max_size: 16,
+            max_backups: 2,
+            encoding: TextEncoding::Utf8,
+        };
+
+        for line in [
+            "1111111111\n",
+            "2222222222\n",
+            "3333333333\n",
+            "4444444444\n",
+        ] {
+            file.append(line, Some("h\n")).await.unwrap();
+        }
+
+        let read = |name: &str| std::fs::read_to_string(dir.join(name)).unwrap();
+        assert_eq!(read("history.csv"), "h\n4444444444\n");
+        assert_eq!(read("history.csv.1"), "h\n3333333333\n");
+        assert_eq!(read("history.csv.2"), "h\n2222222222\n");
+        assert!(!dir.join("history.csv.3").exists());
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
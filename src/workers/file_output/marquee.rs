@@ -0,0 +1,147 @@
+use std::ops::Range;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// A scrolling window over a string.
+///
+/// The width is measured in grapheme clusters, so CJK characters and emoji
+/// (including ZWJ sequences) are never split.
+#[derive(Debug)]
+pub struct Marquee {
+    /// The text followed by the separator.
+    content: String,
+    /// Byte ranges of the graphemes in `content`.
+    graphemes: Vec<Range<usize>>,
+    /// Byte length of the text (without the separator).
+    text_end: usize,
+    /// Number of graphemes belonging to the text.
+    text_len: usize,
+    width: usize,
+    position: usize,
+}
+
+impl Marquee {
+    pub fn new(text: &str, separator: &str, width: usize) -> Self {
+        let content = format!("{text}{separator}");
+        let graphemes: Vec<_> = content
+            .grapheme_indices(true)
+            .map(|(idx, g)| idx..idx + g.len())
+            .collect();
+        let text_len = text.graphemes(true).count();
+
+        Self {
+            content,
+            graphemes,
+            text_end: text.len(),
+            text_len,
+            width,
+            position: 0,
+        }
+    }
+
+    /// Replaces the text and moves the window back to the start.
+    ///
+    /// If the text didn't change, the window stays where it is and `false` is returned.
+    pub fn set_text(&mut self, text: &str) -> bool {
+        if self.content[..self.text_end] == *text {
+            return false;
+        }
+        let separator = self.content[self.text_end..].to_owned();
+        *self = Self::new(text, &separator, self.width);
+        true
+    }
+
+    /// Returns `true` if the text doesn't fit into the window.
+    pub fn is_scrolling(&self) -> bool {
+        self.text_len > self.width
+    }
+
+    /// Moves the window by one grapheme.
+    pub fn advance(&mut self) {
+        if self.is_scrolling() {
+            self.position = (self.position + 1) % self.graphemes.len();
+        }
+    }
+
+    /// Returns the currently visible part of the text.
+    ///
+    /// If the text fits, it's returned as is.
+    pub fn current(&self) -> String {
+        if !self.is_scrolling() {
+            return self.content[..self.text_end].to_owned();
+        }
+
+        let mut window = String::with_capacity(self.width * 4);
+        for range in self
+            .graphemes
+            .iter()
+            .cycle()
+            .skip(self.position)
+            .take(self.width)
+        {
+            window.push_str(&self.content[range.clone()]);
+        }
+        window
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn fits() {
+        let mut marquee = Marquee::new("Artist - Title", " | ", 20);
+        assert!(!marquee.is_scrolling());
+        assert_eq!(marquee.current(), "Artist - Title");
+        marquee.advance();
+        assert_eq!(marquee.current(), "Artist - Title");
+
+        let marquee = Marquee::new("abc", " | ", 3);
+        assert!(!marquee.is_scrolling());
+        assert_eq!(marquee.current(), "abc");
+    }
+
+    #[test]
+    fn scrolls() {
+        let mut marquee = Marquee::new("abcdef", " | ", 4);
+        assert!(marquee.is_scrolling());
+        let mut windows = Vec::new();
+        for _ in 0..10 {
+            windows.push(marquee.current());
+            marquee.advance();
+        }
+        assert_eq!(
+            windows,
+            ["abcd", "bcde", "cdef", "def ", "ef |", "f | ", " | a", "| ab", " abc", "abcd"]
+        );
+    }
+
+    #[test]
+    fn keeps_position() {
+        let mut marquee = Marquee::new("abcdef", " | ", 4);
+        marquee.advance();
+        marquee.advance();
+        assert!(!marquee.set_text("abcdef"));
+        assert_eq!(marquee.current(), "cdef");
+
+        assert!(marquee.set_text("ghijkl"));
+        assert_eq!(marquee.current(), "ghij");
+        marquee.advance();
+        assert_eq!(marquee.current(), "hijk");
+    }
+
+    #[test]
+    fn graphemes() {
+        // 'ｶﾞ' is two code points but one grapheme, the family emoji is a ZWJ sequence
+        let mut marquee = Marquee::new("ｶﾞ曲👨‍👩‍👧名前", "/", 3);
+        assert!(marquee.is_scrolling());
+        assert_eq!(marquee.current(), "ｶﾞ曲👨‍👩‍👧");
+        marquee.advance();
+        assert_eq!(marquee.current(), "曲👨‍👩‍👧名");
+        marquee.advance();
+        assert_eq!(marquee.current(), "👨‍👩‍👧名前");
+        marquee.advance();
+        marquee.advance();
+        assert_eq!(marquee.current(), "前/ｶﾞ");
+    }
+}
@@ -0,0 +1,183 @@
+mod log;
+mod marquee;
+mod sanitize;
+
+use crate::{
+    config::{FileOutputConfig, FileOutputMode},
+    manager,
+    model::{PlayInfo, TimelineInfo},
+    utilities::{
+        format_string::{FormatDescription, InterpolationProvider},
+        fs::write_atomic,
+    },
+    ModuleState,
+};
+use marquee::Marquee;
+use std::{borrow::Cow, fmt, fmt::Write, path::Path, time::Duration};
+use tap::TapFallible;
+use tokio::{sync::watch, time::MissedTickBehavior};
+use tracing::{debug, info, warn};
+
+#[derive(Debug)]
+enum Interpolation {
+    Title,
+    Artist,
+    TitleLatin,
+    ArtistLatin,
+    AlbumName,
+    AlbumTracks,
+    TrackNumber,
+    Source,
+    Duration,
+}
+
+impl InterpolationProvider for Interpolation {
+    type Source = PlayInfo;
+    type ParseError = anyhow::Error;
+    type FormatError = fmt::Error;
+
+    fn parse_provider(name: &str) -> Result<Self, Self::ParseError> {
+        match name {
+            "title" => Ok(Self::Title),
+            "artist" => Ok(Self::Artist),
+            "title-latin" => Ok(Self::TitleLatin),
+            "artist-latin" => Ok(Self::ArtistLatin),
+            "album-name?" => Ok(Self::AlbumName),
+            "album-tracks?" => Ok(Self::AlbumTracks),
+            "track-number?" => Ok(Self::TrackNumber),
+            "source" => Ok(Self::Source),
+            "duration?" => Ok(Self::Duration),
+            x => Err(anyhow::anyhow!("'{}' is not a valid interpolation", x)),
+        }
+    }
+
+    fn format<W: Write>(&self, source: &PlayInfo, f: &mut W) -> Result<(), Self::FormatError> {
+        match self {
+            Interpolation::Title => f.write_str(&source.title),
+            Interpolation::Artist => f.write_str(&source.artist),
+            Interpolation::TitleLatin => {
+                f.write_str(source.title_latin.as_ref().unwrap_or(&source.title))
+            }
+            Interpolation::ArtistLatin => {
+                f.write_str(source.artist_latin.as_ref().unwrap_or(&source.artist))
+            }
+            Interpolation::AlbumName => match source.album {
+                Some(ref album) => f.write_str(&album.title),
+                None => Ok(()),
+            },
+            Interpolation::AlbumTracks => match source.album {
+                Some(ref album) if album.track_count > 0 => write!(f, "{}", album.track_count),
+                _ => Ok(()),
+            },
+            Interpolation::TrackNumber => match source.track_number {
+                Some(n) if n > 0 => write!(f, "{n}"),
+                _ => Ok(()),
+            },
+            Interpolation::Source => f.write_str(&source.source),
+            Interpolation::Duration => match source.timeline {
+                Some(TimelineInfo { duration_ms, .. }) => {
+                    let duration = Duration::from_millis(duration_ms);
+                    write!(
+                        f,
+                        "{}m{}s",
+                        duration.as_secs() / 60,
+                        duration.as_secs() % 60
+                    )
+                }
+                None => Ok(()),
+            },
+        }
+    }
+}
+
+pub async fn output_to_file(config: &FileOutputConfig, rx: watch::Receiver<manager::Event>) {
+    debug!(path = ?config.path, mode = ?config.mode, "Enabled output to file");
+
+    match config.mode {
+        FileOutputMode::Text => output_text(config, rx).await,
+        FileOutputMode::Json => output_json(config, rx).await,
+        FileOutputMode::NdjsonLog | FileOutputMode::CsvLog => log::output_log(config, rx).await,
+    }
+}
+
+async fn output_text(config: &FileOutputConfig, mut rx: watch::Receiver<manager::Event>) {
+    let path = config.path.as_path();
+
+    let format_descr =
+        FormatDescription::<Interpolation>::try_from(Cow::from(config.format.clone()))
+            .tap_err(|e| warn!(erorr = %e, "Invalid format"))
+            .unwrap_or_else(|_| FormatDescription::raw("invalid format"));
+
+    let mut marquee: Option<Marquee> = None;
+    let mut step = tokio::time::interval(Duration::from_millis(
+        config
+            .marquee
+            .as_ref()
+            .map_or(1000, |m| m.interval_ms.max(1)),
+    ));
+    step.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            changed = rx.changed() => {
+                if changed.is_err() {
+                    break;
+                }
+                let formatted = format_event(&rx.borrow().state, &format_descr);
+                let formatted = sanitize::sanitize(&formatted, &config.sanitize, config.encoding);
+                let formatted = formatted.trim_end();
+
+                match config.marquee {
+                    Some(ref marquee_config) => {
+                        // updates of the position or rate don't move the window back
+                        if marquee.as_mut().is_some_and(|m| !m.set_text(formatted)) {
+                            continue;
+                        }
+                        let marquee = marquee.get_or_insert_with(|| {
+                            Marquee::new(formatted, &marquee_config.separator, marquee_config.width)
+                        });
+                        write_file(path, config.encoding.encode(&marquee.current(), true)).await;
+                        step.reset();
+                    }
+                    None => write_file(path, config.encoding.encode(formatted, true)).await,
+                }
+            }
+            _ = step.tick(), if marquee.as_ref().is_some_and(Marquee::is_scrolling) => {
+                if let Some(ref mut marquee) = marquee {
+                    marquee.advance();
+                    write_file(path, config.encoding.encode(&marquee.current(), true)).await;
+                }
+            }
+        }
+    }
+    info!("Channel closed - Stopped file output");
+}
+
+async fn output_json(config: &FileOutputConfig, mut rx: watch::Receiver<manager::Event>) {
+    while rx.changed().await.is_ok() {
+        let json = serde_json::to_vec(&*rx.borrow().state);
+        match json {
+            Ok(json) => write_file(&config.path, &json).await,
+            Err(e) => warn!(error = %e, "Cannot serialize json"),
+        }
+    }
+    info!("Channel closed - Stopped file output");
+}
+
+async fn write_file(path: &Path, contents: impl AsRef<[u8]>) {
+    if let Err(e) = write_atomic(path, contents).await {
+        warn!(error = %e, "Couldn't write to file");
+    }
+}
+
+fn format_event(
+    state: &ModuleState,
+    format_descr: &FormatDescription<Interpolation>,
+) -> Cow<'static, str> {
+    match state {
+        ModuleState::Playing(info) => format_descr
+            .format_to_string(info)
+            .map_or_else(|_| "cannot format".into(), Cow::from),
+        ModuleState::Paused => "".into(),
+    }
+}
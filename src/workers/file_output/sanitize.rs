@@ -0,0 +1,220 @@
+use crate::config::{Normalization, SanitizeConfig, TextEncoding};
+use std::borrow::Cow;
+use unicode_normalization::UnicodeNormalization;
+use unicode_properties::{EmojiStatus, UnicodeEmoji};
+use unicode_segmentation::UnicodeSegmentation;
+
+impl TextEncoding {
+    fn bom(self) -> &'static [u8] {
+        match self {
+            TextEncoding::Utf8 => &[],
+            TextEncoding::Utf8Bom => &[0xEF, 0xBB, 0xBF],
+            TextEncoding::Utf16leBom => &[0xFF, 0xFE],
+        }
+    }
+
+    /// Encodes `text`. If `bom` is `true`, the BOM of the encoding is prepended.
+    pub fn encode(self, text: &str, bom: bool) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.encoded_len(text) + 3);
+        if bom {
+            bytes.extend_from_slice(self.bom());
+        }
+        match self {
+            TextEncoding::Utf8 | TextEncoding::Utf8Bom => bytes.extend_from_slice(text.as_bytes()),
+            TextEncoding::Utf16leBom => {
+                bytes.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+            }
+        }
+        bytes
+    }
+
+    /// The length of `text` in this encoding without the BOM.
+    fn encoded_len(self, text: &str) -> usize {
+        match self {
+            TextEncoding::Utf8 | TextEncoding::Utf8Bom => text.len(),
+            TextEncoding::Utf16leBom => text.encode_utf16().count() * 2,
+        }
+    }
+}
+
+/// Applies all sanitization options to `text`.
+///
+/// The text is normalized first, then characters are stripped and finally it's truncated.
+pub fn sanitize<'a>(
+    text: &'a str,
+    config: &SanitizeConfig,
+    encoding: TextEncoding,
+) -> Cow<'a, str> {
+    let mut text = match config.normalization {
+        Some(Normalization::Nfc) => Cow::Owned(text.nfc().collect()),
+        Some(Normalization::Nfkc) => Cow::Owned(text.nfkc().collect()),
+        None => Cow::Borrowed(text),
+    };
+    if config.strip_control && text.chars().any(is_control) {
+        text = Cow::Owned(text.chars().filter(|c| !is_control(*c)).collect());
+    }
+    if config.strip_emoji && text.graphemes(true).any(is_emoji) {
+        text = Cow::Owned(text.graphemes(true).filter(|g| !is_emoji(g)).collect());
+    }
+    if let Some(max_bytes) = config.max_bytes {
+        if let Some(end) = truncated_end(&text, max_bytes, encoding) {
+            text = match text {
+                Cow::Borrowed(s) => Cow::Borrowed(&s[..end]),
+                Cow::Owned(mut s) => {
+                    s.truncate(end);
+                    Cow::Owned(s)
+                }
+            };
+        }
+    }
+    text
+}
+
+/// Control characters (except line feeds) and characters changing the text direction.
+fn is_control(c: char) -> bool {
+    (c.is_control() && c != '\n')
+        || matches!(
+            c,
+            '\u{061C}' | '\u{200E}' | '\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}'
+        )
+}
+
+/// Checks if a grapheme is displayed as an emoji.
+///
+/// Characters like digits or `©` can be emoji as well, but they're only displayed as one
+/// if they're followed by a variation selector or a keycap.
+fn is_emoji(grapheme: &str) -> bool {
+    grapheme.chars().any(|c| {
+        c == '\u{FE0F}'
+            || c == '\u{20E3}'
+            || matches!(
+                c.emoji_status(),
+                EmojiStatus::EmojiPresentation
+                    | EmojiStatus::EmojiPresentationAndModifierBase
+                    | EmojiStatus::EmojiPresentationAndEmojiComponent
+                    | EmojiStatus::EmojiPresentationAndModifierAndEmojiComponent
+            )
+    })
+}
+
+/// Returns the byte index at which `text` has to be cut to fit into `max_bytes` when encoded.
+/// Graphemes are never split.
+fn truncated_end(text: &str, max_bytes: usize, encoding: TextEncoding) -> Option<usize> {
+    let mut len = 0;
+    for (idx, grapheme) in text.grapheme_indices(true) {
+        len += encoding.encoded_len(grapheme);
+        if len > max_bytes {
+            return Some(idx);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn config() -> SanitizeConfig {
+        SanitizeConfig::default()
+    }
+
+    #[test]
+    fn encodings() {
+        assert_eq!(TextEncoding::Utf8.encode("aä", true), b"a\xC3\xA4");
+        assert_eq!(
+            TextEncoding::Utf8Bom.encode("aä", true),
+            b"\xEF\xBB\xBFa\xC3\xA4"
+        );
+        assert_eq!(TextEncoding::Utf8Bom.encode("aä", false), b"a\xC3\xA4");
+        assert_eq!(
+            TextEncoding::Utf16leBom.encode("aä🎵", true),
+            b"\xFF\xFEa\x00\xE4\x00\x3C\xD8\xB5\xDF"
+        );
+        assert_eq!(TextEncoding::Utf16leBom.encode("a", false), b"a\x00");
+    }
+
+    #[test]
+    fn unchanged() {
+        let text = "Artist - Title 🎵\u{202E}";
+        assert!(matches!(
+            sanitize(text, &config(), TextEncoding::Utf8),
+            Cow::Borrowed(s) if s == text
+        ));
+    }
+
+    #[test]
+    fn strip_emoji() {
+        let config = SanitizeConfig {
+            strip_emoji: true,
+            ..config()
+        };
+        for (input, expected) in [
+            ("Title 🎵", "Title "),
+            ("👨‍👩‍👧 family", " family"),
+            ("🇯🇵 flag", " flag"),
+            ("👍🏽 skin tone", " skin tone"),
+            ("❤️ heart", " heart"),
+            ("1️⃣ keycap", " keycap"),
+            ("Track 1 #2 © ❤ ü 曲", "Track 1 #2 © ❤ ü 曲"),
+        ] {
+            assert_eq!(sanitize(input, &config, TextEncoding::Utf8), expected);
+        }
+    }
+
+    #[test]
+    fn strip_control() {
+        let config = SanitizeConfig {
+            strip_control: true,
+            ..config()
+        };
+        for (input, expected) in [
+            ("a\u{0}b\u{7}c\td\re", "abcde"),
+            ("line\nbreak", "line\nbreak"),
+            ("\u{202E}txet\u{202C} \u{2067}a\u{2069}\u{200F}", "txet a"),
+            // ZWJ is required for emoji
+            ("👨‍👩‍👧", "👨‍👩‍👧"),
+        ] {
+            assert_eq!(sanitize(input, &config, TextEncoding::Utf8), expected);
+        }
+    }
+
+    #[test]
+    fn normalization() {
+        let canonical = SanitizeConfig {
+            normalization: Some(Normalization::Nfc),
+            ..config()
+        };
+        let compatibility = SanitizeConfig {
+            normalization: Some(Normalization::Nfkc),
+            ..config()
+        };
+        // 'e' + combining acute accent
+        assert_eq!(sanitize("e\u{301}", &canonical, TextEncoding::Utf8), "é");
+        assert_eq!(sanitize("ﬁ ｶﾞ ①", &canonical, TextEncoding::Utf8), "ﬁ ｶﾞ ①");
+        assert_eq!(
+            sanitize("ﬁ ｶﾞ ①", &compatibility, TextEncoding::Utf8),
+            "fi ガ 1"
+        );
+    }
+
+    #[test]
+    fn max_bytes() {
+        let config = SanitizeConfig {
+            max_bytes: Some(5),
+            ..config()
+        };
+        assert_eq!(sanitize("abc", &config, TextEncoding::Utf8), "abc");
+        assert_eq!(sanitize("abcdefg", &config, TextEncoding::Utf8), "abcde");
+        // 'ä' is two bytes and must not be split
+        assert_eq!(sanitize("abcdä", &config, TextEncoding::Utf8), "abcd");
+        // the BOM isn't counted
+        assert_eq!(sanitize("abcdefg", &config, TextEncoding::Utf8Bom), "abcde");
+        // graphemes are never split
+        assert_eq!(
+            sanitize("ae\u{301}bc", &config, TextEncoding::Utf8),
+            "ae\u{301}b"
+        );
+        assert_eq!(sanitize("abcdefg", &config, TextEncoding::Utf16leBom), "ab");
+        assert_eq!(sanitize("a🎵", &config, TextEncoding::Utf16leBom), "a");
+    }
+}
@@ -1,5 +1,8 @@
 use crate::{
-    actors::manager::{CreateModule, Manager, RemoveModule, UpdateModule},
+    actors::manager::{
+        ControlAction, ControlError, Controller, CreateModule, Manager, RemoveModule,
+        SetController, UpdateModule,
+    },
     config::CONFIG,
     image_store::{ImageStore, SlotRef},
     model::{AlbumInfo, ImageInfo, InternalImage, ModuleState, PlayInfo, TimelineInfo},
@@ -8,6 +11,7 @@ use crate::{
 use ::gsmtc::{ManagerEvent, SessionManager, SessionUpdateEvent};
 use actix::Addr;
 use anyhow::Result as AnyResult;
+use futures::future::BoxFuture;
 use gsmtc::{Image, PlaybackStatus, SessionModel};
 use serde::{Deserialize, Serialize};
 use std::sync::{Arc, RwLock};
@@ -85,6 +89,34 @@ impl GsmtcFilterData {
     }
 }
 
+impl Controller for gsmtc::Controls {
+    fn control(&self, action: ControlAction) -> BoxFuture<'static, Result<(), ControlError>> {
+        // GSMTC positions are in 100ns ticks
+        let command = match action {
+            ControlAction::Play => gsmtc::Command::Play,
+            ControlAction::Pause => gsmtc::Command::Pause,
+            ControlAction::Toggle => gsmtc::Command::PlayPause,
+            ControlAction::Next => gsmtc::Command::Next,
+            ControlAction::Previous => gsmtc::Command::Previous,
+            ControlAction::Seek { offset_ms } => {
+                gsmtc::Command::Seek(offset_ms.saturating_mul(10_000))
+            }
+            ControlAction::SeekTo { position_ms } => gsmtc::Command::SeekTo(
+                i64::try_from(position_ms)
+                    .unwrap_or(i64::MAX)
+                    .saturating_mul(10_000),
+            ),
+        };
+        let controls = self.clone();
+        Box::pin(async move {
+            controls.execute(command).await.map_err(|e| match e {
+                gsmtc::ControlError::NotSupported => ControlError::Unsupported,
+                gsmtc::ControlError::Failed(e) => ControlError::Failed(e.to_string()),
+            })
+        })
+    }
+}
+
 #[derive(Debug)]
 struct GsmtcWorker {
     manager: Addr<Manager>,
@@ -105,7 +137,13 @@ pub async fn start_spawning(
     tokio::spawn(
         async move {
             while let Some(evt) = rx.recv().await {
-                if let ManagerEvent::SessionCreated { rx, source, .. } = evt {
+                if let ManagerEvent::SessionCreated {
+                    rx,
+                    source,
+                    controls,
+                    ..
+                } = evt
+                {
                     if !CONFIG.modules.gsmtc.filter.pass_filter(&source) {
                         event!(Level::DEBUG, "Ignoring {} as it's filtered", source);
                         continue;
@@ -117,6 +155,10 @@ pub async fn start_spawning(
                             "Creating GSMTC worker: module-id: {}",
                             module_id
                         );
+                        manager.do_send(SetController {
+                            id: module_id,
+                            controller: Box::new(controls),
+                        });
                         let image_id = SlotRef::new(&image_store);
                         tokio::spawn(
                             GsmtcWorker {
@@ -233,6 +275,8 @@ fn convert_model(from: SessionModel, image: Option<ImageInfo>) -> ModuleState {
                     #[allow(clippy::cast_possible_truncation)]
                     rate: playback.rate as f32,
                 }),
+            title_latin: None,
+            artist_latin: None,
         }),
         _ => ModuleState::Paused,
     }
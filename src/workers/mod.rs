@@ -1,3 +1,5 @@
+pub mod cover_output;
+pub mod delay;
 pub mod file_output;
 
 #[cfg(windows)]